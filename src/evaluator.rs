@@ -0,0 +1,244 @@
+/// This module contains the Evaluator, an embeddable formula engine that bundles an
+/// evaluation context with the functions registered by the host application.
+// Internal modules
+use crate::parsemath::ast::{self, EvalError, Expr};
+use crate::parsemath::context::Context;
+use crate::parsemath::functions::{Arity, Function};
+use crate::parsemath::numeric::{self, Evaluated, Numeric};
+use crate::parsemath::operators::Operator;
+use crate::parsemath::parser::ParseOptions;
+use crate::parsemath::value::Value;
+use crate::Error;
+
+// Evaluator struct, built up with variables and host functions before evaluating expressions
+#[derive(Debug, Clone, Default)]
+pub struct Evaluator {
+    ctx: Context,
+    options: ParseOptions,
+}
+
+impl Evaluator {
+    // Create an evaluator with only the built-in functions available
+    pub fn new() -> Self {
+        Evaluator::default()
+    }
+
+    // Bind a variable
+    pub fn with_var(mut self, name: impl Into<String>, value: impl Into<Value>) -> Self {
+        self.ctx.set_var(name, value);
+        self
+    }
+
+    // Define a new constant or override a built-in one
+    pub fn with_constant(mut self, name: impl Into<String>, value: f64) -> Self {
+        self.ctx.set_constant(name, value);
+        self
+    }
+
+    // Prevent variables from shadowing constants
+    pub fn read_only_constants(mut self) -> Self {
+        self.ctx.set_constants_read_only(true);
+        self
+    }
+
+    // Skip whitespace inside numbers instead of rejecting "1 000"
+    pub fn lenient_numbers(mut self) -> Self {
+        self.options.lenient_numbers = true;
+        self
+    }
+
+    // Take percentages of the left operand, so 100 + 10% is 110
+    pub fn calculator_percent(mut self) -> Self {
+        self.options.calculator_percent = true;
+        self
+    }
+
+    // Reject juxtaposed operands such as 2pi instead of multiplying them
+    pub fn without_implicit_multiplication(mut self) -> Self {
+        self.options.implicit_multiplication = false;
+        self
+    }
+
+    // Register a host function taking `arity` arguments, e.g. register_fn("tax", 1, ...).
+    // Pass Arity::AtLeast(n) or Arity::Range(min, max) for variadic functions.
+    // The closure returns None when its arguments are outside of its domain.
+    pub fn register_fn<F>(
+        mut self,
+        name: impl Into<String>,
+        arity: impl Into<Arity>,
+        func: F,
+    ) -> Self
+    where
+        F: Fn(&[f64]) -> Option<f64> + Send + Sync + 'static,
+    {
+        self.ctx.set_fn(name, Function::new(arity, func));
+        self
+    }
+
+    // Register a custom operator, e.g. Operator::infix("±", OperPrec::AddSub, Assoc::Left, ...)
+    pub fn register_operator(mut self, operator: Operator) -> Self {
+        self.ctx.set_operator(operator);
+        self
+    }
+
+    // Context the expressions are evaluated against
+    pub fn context(&self) -> &Context {
+        &self.ctx
+    }
+
+    pub fn context_mut(&mut self) -> &mut Context {
+        &mut self.ctx
+    }
+
+    // Evaluate an already parsed expression
+    pub fn eval(&self, expr: &Expr) -> Result<Value, Error> {
+        Ok(ast::eval_with(expr, &self.ctx)?)
+    }
+
+    // Parse an expression with the options and custom operators of this evaluator
+    pub fn parse(&self, expr: &str) -> Result<Expr, Error> {
+        crate::parse_with_operators(expr, self.options, self.ctx.operators())
+    }
+
+    // Parse and evaluate an expression
+    pub fn evaluate(&self, expr: &str) -> Result<Value, Error> {
+        let ast = self.parse(expr)?;
+        self.eval(&ast)
+    }
+
+    // Parse and evaluate an expression exactly with another number type,
+    // e.g. evaluate_as::<i64>("2^62 + 1"). Overflow and inexact division are errors.
+    pub fn evaluate_as<N: Numeric>(&self, expr: &str) -> Result<Value<N>, Error> {
+        let ast = self.parse_exact(expr)?;
+        Ok(numeric::eval_with(&ast, &self.ctx)?)
+    }
+
+    // Like evaluate_as, but an operation without an exact result, such as 7 / 2 for integers,
+    // promotes the whole expression to f64 instead of failing
+    pub fn evaluate_promoting<N: Numeric>(&self, expr: &str) -> Result<Evaluated<N>, Error> {
+        let ast = self.parse_exact(expr)?;
+        match numeric::eval_with(&ast, &self.ctx) {
+            Ok(value) => Ok(Evaluated::Exact(value)),
+            Err(EvalError::InexactResult { .. }) => Ok(Evaluated::Promoted(self.eval(&ast)?)),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    // Number types other than f64 read decimal literals from their digits, so 0.1 stays exact
+    fn parse_exact(&self, expr: &str) -> Result<Expr, Error> {
+        let options = ParseOptions { exact_decimals: true, ..self.options };
+        crate::parse_with_operators(expr, options, self.ctx.operators())
+    }
+
+    // Run an already parsed statement, keeping the variables it assigns.
+    // The result is also stored in `ans` for the next statement.
+    pub fn exec(&mut self, expr: &Expr) -> Result<Value, Error> {
+        let value = ast::exec(expr, &mut self.ctx)?;
+        self.ctx.set_var("ans", value);
+        Ok(value)
+    }
+
+    // Parse and run a statement such as x = 3*4 or x^2, see exec
+    pub fn run(&mut self, expr: &str) -> Result<Value, Error> {
+        let ast = self.parse(expr)?;
+        self.exec(&ast)
+    }
+}
+
+// Unit tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_register_fn() {
+        let evaluator = Evaluator::new()
+            .with_var("price", 100.0)
+            .register_fn("tax", 1, |a| Some(a[0] * 0.2))
+            .register_fn("avg", Arity::AtLeast(1), |a| {
+                Some(a.iter().sum::<f64>() / a.len() as f64)
+            });
+        assert_eq!(evaluator.evaluate("price + tax(price)").unwrap(), 120.0);
+        assert_eq!(evaluator.evaluate("avg(1, 2, 3, 6)").unwrap(), 3.0);
+        assert!(matches!(
+            evaluator.evaluate("avg()"),
+            Err(Error::Eval(EvalError::ArityMismatch { found: 0, .. }))
+        ));
+    }
+
+    #[test]
+    fn test_constants() {
+        let evaluator = Evaluator::new().with_constant("g", 9.81).with_var("pi", 3.0);
+        assert_eq!(evaluator.evaluate("pi * 2").unwrap(), 6.0);
+        assert_eq!(evaluator.evaluate("g * 2").unwrap(), 19.62);
+
+        let evaluator = evaluator.read_only_constants();
+        assert_eq!(evaluator.evaluate("pi").unwrap(), std::f64::consts::PI);
+    }
+
+    #[test]
+    fn test_run_session() {
+        let mut session = Evaluator::new();
+        assert_eq!(session.run("x = 3*4").unwrap(), 12.0);
+        assert_eq!(session.run("x^2").unwrap(), 144.0);
+        assert_eq!(session.run("ans / 2").unwrap(), 72.0);
+        assert_eq!(session.run("a = b = ans + 1").unwrap(), 73.0);
+        assert_eq!(session.context().get_var("b"), Some(Value::Number(73.0)));
+
+        // A failed statement leaves the session untouched
+        assert!(session.run("y = 1 / 0").is_err());
+        assert_eq!(session.context().get_var("y"), None);
+        assert_eq!(session.run("ans").unwrap(), 73.0);
+
+        // Plain evaluation does not mutate the evaluator
+        assert!(matches!(
+            session.evaluate("z = 1"),
+            Err(Error::Eval(EvalError::AssignWithoutSession { .. }))
+        ));
+        let mut session = session.read_only_constants();
+        assert!(matches!(
+            session.run("pi = 3"),
+            Err(Error::Eval(EvalError::AssignToConstant { .. }))
+        ));
+    }
+
+    #[test]
+    fn test_lenient_numbers() {
+        assert!(matches!(Evaluator::new().evaluate("1 000 + 1"), Err(Error::Parse(_))));
+        assert_eq!(Evaluator::new().lenient_numbers().evaluate("1 000 + 1").unwrap(), 1001.0);
+    }
+
+    #[test]
+    fn test_implicit_multiplication() {
+        let evaluator = Evaluator::new().with_var("x", 3.0);
+        assert_eq!(evaluator.evaluate("2x^2 + (x)(2)").unwrap(), 24.0);
+        let evaluator = evaluator.without_implicit_multiplication();
+        assert!(matches!(evaluator.evaluate("2x"), Err(Error::Parse(_))));
+    }
+
+    #[test]
+    fn test_calculator_percent() {
+        assert_eq!(Evaluator::new().evaluate("100 + 10%").unwrap(), 100.1);
+        assert_eq!(Evaluator::new().evaluate("20% - 5").unwrap(), -4.8);
+        let evaluator = Evaluator::new().calculator_percent();
+        assert_eq!(evaluator.evaluate("100 + 10%").unwrap(), 110.0);
+        assert_eq!(evaluator.evaluate("80 - 25%").unwrap(), 60.0);
+        assert_eq!(evaluator.evaluate("80 * 25%").unwrap(), 20.0);
+        assert_eq!(evaluator.evaluate("100 + 10% - 5").unwrap(), 105.0);
+        assert_eq!(evaluator.evaluate("100 - 10% + 1").unwrap(), 91.0);
+    }
+
+    #[test]
+    fn test_host_function_errors() {
+        let evaluator = Evaluator::new()
+            .register_fn("lookup", 1, |a| if a[0] == 1.0 { Some(42.0) } else { None })
+            .register_fn("sqrt", 1, |a| Some(a[0].abs().sqrt()));
+        assert_eq!(evaluator.evaluate("lookup(1)").unwrap(), 42.0);
+        assert!(matches!(
+            evaluator.evaluate("lookup(2)"),
+            Err(Error::Eval(EvalError::DomainError { .. }))
+        ));
+        // Host functions shadow built-ins of the same name
+        assert_eq!(evaluator.evaluate("sqrt(-4)").unwrap(), 2.0);
+    }
+}
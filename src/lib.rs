@@ -1 +1,107 @@
-pub mod parsemath;
\ No newline at end of file
+/// Library entry point for the arithmetic expression evaluator.
+/// The `parsemath` module holds the tokenizer, parser and AST evaluator, while the
+/// functions below provide a stable facade for applications embedding the evaluator.
+pub mod evaluator;
+pub mod parsemath;
+
+pub use evaluator::Evaluator;
+
+// Standard lib
+use std::fmt;
+
+use parsemath::ast::{self, EvalError, Expr};
+use parsemath::context::Context;
+use parsemath::numeric::{self, Numeric};
+use parsemath::span;
+use parsemath::value::Value;
+use parsemath::operators::OperatorTable;
+use parsemath::parser::{ParseError, ParseOptions, Parser};
+
+// Error returned by the library facade, keeping parse and evaluation failures apart
+#[derive(Debug)]
+pub enum Error {
+    Parse(ParseError),
+    Eval(EvalError),
+}
+
+impl Error {
+    // Render the error against the source expression as a caret-style diagnostic
+    pub fn render(&self, source: &str) -> String {
+        let span = match self {
+            Error::Parse(e) => e.span(),
+            Error::Eval(e) => e.span(),
+        };
+        span::render(source, span, &self.to_string())
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Parse(e) => write!(f, "{}", e),
+            Error::Eval(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Parse(e) => Some(e),
+            Error::Eval(e) => Some(e),
+        }
+    }
+}
+
+impl From<ParseError> for Error {
+    fn from(err: ParseError) -> Self {
+        Error::Parse(err)
+    }
+}
+
+impl From<EvalError> for Error {
+    fn from(err: EvalError) -> Self {
+        Error::Eval(err)
+    }
+}
+
+// Parse an arithmetic expression into an AST without evaluating it
+pub fn parse(expr: &str) -> Result<Expr, Error> {
+    parse_with_options(expr, ParseOptions::default())
+}
+
+// Parse an arithmetic expression with non-default options, e.g. lenient whitespace in numbers
+pub fn parse_with_options(expr: &str, options: ParseOptions) -> Result<Expr, Error> {
+    let mut math_parser = Parser::with_options(expr, options)?;
+    Ok(math_parser.parse()?)
+}
+
+// Parse an arithmetic expression that may use custom operators
+pub fn parse_with_operators(
+    expr: &str,
+    options: ParseOptions,
+    operators: &OperatorTable,
+) -> Result<Expr, Error> {
+    let mut math_parser = Parser::with_operators(expr, options, operators)?;
+    Ok(math_parser.parse()?)
+}
+
+// Parse and evaluate an arithmetic expression
+pub fn evaluate(expr: &str) -> Result<Value, Error> {
+    let ast = parse(expr)?;
+    Ok(ast::eval(ast)?)
+}
+
+// Parse and evaluate an arithmetic expression, resolving variables against the context
+pub fn evaluate_with(expr: &str, ctx: &Context) -> Result<Value, Error> {
+    let ast = parse_with_operators(expr, ParseOptions::default(), ctx.operators())?;
+    Ok(ast::eval_with(&ast, ctx)?)
+}
+
+// Parse and evaluate an arithmetic expression exactly with another number type,
+// e.g. evaluate_as::<i64>("2^62 + 1"), reporting overflow and inexact division as errors
+pub fn evaluate_as<N: Numeric>(expr: &str) -> Result<Value<N>, Error> {
+    let options = ParseOptions { exact_decimals: true, ..ParseOptions::default() };
+    let ast = parse_with_options(expr, options)?;
+    Ok(numeric::eval_with(&ast, &Context::new())?)
+}
@@ -2,19 +2,16 @@
 // Standard library
 use std::io;
 
-// code for arithmetic expression evaluation is in parsemath module
-mod parsemath;
-use parsemath::ast;
-use parsemath::parser::{ParseError, Parser};
+// code for arithmetic expression evaluation lives in the expression_eval library
+use expression_eval::parsemath::value::Value;
+use expression_eval::{Error, Evaluator};
 
-// Function to invoke Parser and evaluate expression
-fn evaluate(expr: String) -> Result<f64, ParseError> {
-    let expr = expr.split_whitespace().collect::<String>(); // remove whitespace chars
-    let mut math_parser = Parser::new(&expr)?;
-    let ast = math_parser.parse()?;
+// Function to invoke Parser and evaluate expression, keeping assigned variables in the session
+fn evaluate(session: &mut Evaluator, expr: &str) -> Result<Value, Error> {
+    let ast = session.parse(expr)?;
     println!("The generated AST is {:?}", ast);
 
-    Ok(ast::eval(ast)?)
+    session.exec(&ast)
 }
 
 // Main function reads arithmetic expression from command-line and displays result and error.
@@ -23,17 +20,28 @@ fn evaluate(expr: String) -> Result<f64, ParseError> {
 fn main() {
     println!("Hello! Welcome to Arithmetic expression evaluator.");
     println!("You can calculate value for expression such as 2*3+(4-5)+2^3/4. ");
-    println!("Allowed numbers: positive, negative and decimals.");
-    println!("Supported operations: Add, Subtract, Multiply, Divide, PowerOf(^). ");
+    println!("Allowed numbers: positive, negative, decimals, 6.022e23, 1_000_000 and 0x/0o/0b integers.");
+    println!("Supported operations: Add, Subtract, Multiply, Divide, PowerOf(^), Modulo(% or mod), Remainder(rem), FloorDivide(//). ");
+    println!("Implicit multiplication: 2pi, 3sqrt(2), (1+2)(3+4). ");
+    println!("Postfix operators: factorial (5!) and percent (20%). ");
+    println!("Bitwise operations on integers: &, |, xor, ~, <<, >>. ");
+    println!("Comparisons and logic: ==, !=, <, <=, >, >=, &&, ||, !, true, false. ");
+    println!("Conditionals: cond ? a : b, if(cond, a, b). ");
+    println!("Supported functions: sin, cos, tan, sqrt, exp, ln, log, abs, min, max, ... ");
+    println!("Variables: x = 3*4 assigns x for the following lines, ans holds the last result. ");
     println!("Enter your arithmetic expression below:");
+    let mut session = Evaluator::new();
     loop {
         let mut input = String::new();
         match io::stdin().read_line(&mut input) {
+            Ok(0) => break, // end of input
             Ok(_) => {
-                match evaluate(input) {
-                    Ok(val) => println!("The computed number is {}\n", val),
-                    Err(_) => {
-                        println!("Error in evaluating expression. Please enter valid expression\n");
+                let expr = input.trim_end(); // whitespace between tokens is handled by the tokenizer
+                match evaluate(&mut session, expr) {
+                    Ok(val) => println!("The computed value is {}\n", val),
+                    Err(error) => {
+                        // Point at the offending part of the expression
+                        println!("{}\n", error.render(expr));
                     }
                 };
             }
@@ -1,6 +1,14 @@
 /// This program contains list of valid AST nodes that can be constructed and also evaluates an AST to compute a value
 // Standard lib
-use std::error;
+use std::{error, fmt};
+
+// Internal modules
+use super::context::Context;
+use super::functions::Arity;
+use super::numeric;
+use super::operators::Fixity;
+use super::span::Span;
+use super::value::Value;
 
 //structs
 
@@ -8,87 +16,230 @@ use std::error;
 // Tokens can be arithmetic operators or a Number
 #[derive(Debug, Clone, PartialEq)]
 pub enum Node {
-    // WARNING: Bitwise And and Or operation only works on integer value
-    And(Box<Node>, Box<Node>),
-    Or(Box<Node>, Box<Node>),
-
-    Add(Box<Node>, Box<Node>),
-    Subtract(Box<Node>, Box<Node>),
-    Multiply(Box<Node>, Box<Node>),
-    Divide(Box<Node>, Box<Node>),
-    Caret(Box<Node>, Box<Node>),
-    Negative(Box<Node>),
+    // WARNING: Bitwise operations only work on integer values
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Xor(Box<Expr>, Box<Expr>),
+    BitNot(Box<Expr>),
+    ShiftLeft(Box<Expr>, Box<Expr>),
+    ShiftRight(Box<Expr>, Box<Expr>),
+
+    Add(Box<Expr>, Box<Expr>),
+    Subtract(Box<Expr>, Box<Expr>),
+    Multiply(Box<Expr>, Box<Expr>),
+    Divide(Box<Expr>, Box<Expr>),
+    // Floor division, a // b == floor(a / b)
+    FloorDivide(Box<Expr>, Box<Expr>),
+    // Floored modulo (`%` and `mod`): the result takes the sign of the divisor, so that
+    // a == (a // b) * b + a % b, e.g. -7 % 3 == 2
+    Modulo(Box<Expr>, Box<Expr>),
+    // Truncated remainder (`rem`): the result takes the sign of the dividend, e.g. -7 rem 3 == -1
+    Remainder(Box<Expr>, Box<Expr>),
+    Caret(Box<Expr>, Box<Expr>),
+    Positive(Box<Expr>),
+    Negative(Box<Expr>),
+    // Postfix n!, extended to non-integers through the gamma function
+    Factorial(Box<Expr>),
+    // Postfix x%, i.e. x / 100
+    Percent(Box<Expr>),
+    // Calculator percentages a + b% and a - b%, i.e. a * (1 + b/100) and a * (1 - b/100)
+    AddPercent(Box<Expr>, Box<Expr>),
+    SubtractPercent(Box<Expr>, Box<Expr>),
+
+    // Comparison and logical operators produce booleans
+    Equal(Box<Expr>, Box<Expr>),
+    NotEqual(Box<Expr>, Box<Expr>),
+    Less(Box<Expr>, Box<Expr>),
+    LessEqual(Box<Expr>, Box<Expr>),
+    Greater(Box<Expr>, Box<Expr>),
+    GreaterEqual(Box<Expr>, Box<Expr>),
+    LogicalAnd(Box<Expr>, Box<Expr>),
+    LogicalOr(Box<Expr>, Box<Expr>),
+    Not(Box<Expr>),
+
+    // cond ? a : b, only the taken branch is evaluated
+    Conditional(Box<Expr>, Box<Expr>, Box<Expr>),
+
     Number(f64),
+    // Decimal literal with the digits as typed, e.g. 0.10, see ParseOptions::exact_decimals
+    Decimal(String),
+    // Integer literal written in hex, octal or binary, e.g. 0xFF
+    Integer(u128),
+    Bool(bool),
+    Variable(String),
+    Call(String, Vec<Expr>),
+    Operator(Fixity, String, Vec<Expr>), // custom operator registered at runtime
+    // Statement binding a variable, e.g. x = 3*4. Only evaluated by exec.
+    Assign(String, Box<Expr>),
 }
 
-// Given an AST, calculate the numeric value.
-pub fn eval(expr: Node) -> Result<f64, Box<dyn error::Error>> {
-    use self::Node::*;
-    match expr {
-        Number(i) => Ok(i),
-        Negative(expr) => Ok(-eval(*expr)?),
-        Add(expr1, expr2) => Ok(eval(*expr1)? + eval(*expr2)?),
-        Subtract(expr1, expr2) => Ok(eval(*expr1)? - eval(*expr2)?),
-        Multiply(expr1, expr2) => Ok(eval(*expr1)? * eval(*expr2)?),
-        Divide(expr1, expr2) => {
-            let denom = eval(*expr2)?;
-            if denom.abs() < f64::EPSILON {
-                Err(("Division by zero").into())
-            } else {
-                Ok(eval(*expr1)? / denom)
-            }
-        },
-        Caret(expr1, expr2) => {
-            let base_exp = eval(*expr1)?;
-            let pow_exp = eval(*expr2)?;
-
-            if base_exp == 0.0 && pow_exp < 0.0 {
-                return Err("0^negative is undefined".into());
-            } 
-            
-            if base_exp < 0.0 && (pow_exp.fract().abs() > f64::EPSILON) {
-                return Err("Negative base with fractional exponent".into());
-            }  
-
-            let res = base_exp.powf(pow_exp);
-            if res.is_infinite() {
-                return Err("Overflow in exponentiation".into());
+// AST node together with the span of source text it was parsed from
+#[derive(Debug, Clone)]
+pub struct Expr {
+    pub node: Node,
+    pub span: Span,
+}
+
+impl Expr {
+    pub fn new(node: Node, span: Span) -> Self {
+        Expr { node, span }
+    }
+}
+
+// Nodes built by hand (e.g. in tests) carry an empty span
+impl From<Node> for Expr {
+    fn from(node: Node) -> Self {
+        Expr::new(node, Span::default())
+    }
+}
+
+// Two expressions are equal when their trees are equal; spans only locate them in the source
+impl PartialEq for Expr {
+    fn eq(&self, other: &Self) -> bool {
+        self.node == other.node
+    }
+}
+
+// Given an AST, calculate its value.
+pub fn eval(expr: Expr) -> Result<Value, EvalError> {
+    eval_with(&expr, &Context::new())
+}
+
+// Run a statement, binding the variables it assigns in the context, and return its value
+pub fn exec(expr: &Expr, ctx: &mut Context) -> Result<Value, EvalError> {
+    match &expr.node {
+        Node::Assign(name, value) => {
+            // A read-only constant would keep shadowing the new variable
+            if ctx.constants_read_only() && ctx.get_constant(name).is_some() {
+                return Err(EvalError::AssignToConstant { name: name.clone(), span: expr.span });
             }
+            let value = exec(value, ctx)?;
+            ctx.set_var(name.clone(), value);
+            Ok(value)
+        }
+        _ => eval_with(expr, ctx),
+    }
+}
+
+// Given an AST, calculate its value, resolving variables against the context.
+pub fn eval_with(expr: &Expr, ctx: &Context) -> Result<Value, EvalError> {
+    numeric::eval_with::<f64>(expr, ctx)
+}
+
+// Custom error type for AST evaluation, carrying the operands that caused the failure
+// and the span of the operation in the source expression
+#[derive(Debug, Clone, PartialEq)]
+pub enum EvalError {
+    DivisionByZero { dividend: f64, span: Span },
+    ZeroToNegativePower { exponent: f64, span: Span },
+    NegativeBaseFractionalExponent { base: f64, exponent: f64, span: Span },
+    Overflow { op: &'static str, lhs: f64, rhs: f64, span: Span },
+    FactorialOverflow { value: f64, span: Span },
+    NonIntegerBitwiseOperand { op: &'static str, value: f64, span: Span },
+    ShiftOutOfRange { op: &'static str, amount: i64, span: Span },
+    NumericOverflow { op: &'static str, ty: &'static str, span: Span },
+    InexactResult { op: &'static str, ty: &'static str, span: Span },
+    NotRepresentable { value: f64, ty: &'static str, span: Span },
+    InvalidLiteral { literal: String, span: Span },
+    TypeMismatch { expected: &'static str, found: &'static str, span: Span },
+    UnknownVariable { name: String, span: Span },
+    UnknownFunction { name: String, span: Span },
+    UnknownOperator { symbol: String, span: Span },
+    AssignToConstant { name: String, span: Span },
+    AssignWithoutSession { name: String, span: Span },
+    ArityMismatch { name: String, expected: Arity, found: usize, span: Span },
+    DomainError { name: String, args: Vec<f64>, span: Span },
+}
+
+impl EvalError {
+    // Location of the failing operation in the source expression
+    pub fn span(&self) -> Span {
+        match self {
+            EvalError::DivisionByZero { span, .. }
+            | EvalError::ZeroToNegativePower { span, .. }
+            | EvalError::NegativeBaseFractionalExponent { span, .. }
+            | EvalError::Overflow { span, .. }
+            | EvalError::FactorialOverflow { span, .. }
+            | EvalError::NonIntegerBitwiseOperand { span, .. }
+            | EvalError::ShiftOutOfRange { span, .. }
+            | EvalError::NumericOverflow { span, .. }
+            | EvalError::InexactResult { span, .. }
+            | EvalError::NotRepresentable { span, .. }
+            | EvalError::InvalidLiteral { span, .. }
+            | EvalError::TypeMismatch { span, .. }
+            | EvalError::UnknownVariable { span, .. }
+            | EvalError::UnknownFunction { span, .. }
+            | EvalError::UnknownOperator { span, .. }
+            | EvalError::AssignToConstant { span, .. }
+            | EvalError::AssignWithoutSession { span, .. }
+            | EvalError::ArityMismatch { span, .. }
+            | EvalError::DomainError { span, .. } => *span,
+        }
+    }
+}
 
-            Ok(res)
-        },
-        And(expr1, expr2) => {
-            let left_exp = eval(*expr1)?;
-            let right_exp = eval(*expr2)?;
-            if left_exp.fract() == 0.0 && right_exp.fract() == 0.0 
-                && left_exp >= (i64::MIN as f64)
-                && right_exp >= (i64::MIN as f64)
-                && left_exp <= (i64::MAX as f64) 
-                && right_exp <= (i64::MAX as f64) 
-            {
-                Ok((left_exp as i64 & right_exp as i64) as f64)
+impl fmt::Display for EvalError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            EvalError::DivisionByZero { dividend, .. } => {
+                write!(f, "Division by zero ({} / 0)", dividend)
             }
-            else {
-                Err("Cannot perform bitwise AND with the following expressions".into())
+            EvalError::ZeroToNegativePower { exponent, .. } => {
+                write!(f, "0^negative is undefined (0 ^ {})", exponent)
             }
-        },
-        Or(expr1, expr2) => {
-            let left_exp = eval(*expr1)?;
-            let right_exp = eval(*expr2)?;
-            if left_exp.fract() == 0.0 && right_exp.fract() == 0.0 
-                && left_exp >= (i64::MIN as f64)
-                && right_exp >= (i64::MIN as f64)
-                && left_exp <= (i64::MAX as f64) 
-                && right_exp <= (i64::MAX as f64) 
-            {
-                Ok((left_exp as i64 | right_exp as i64) as f64)
-            } else {
-                Err("Cannot perform bitwise OR with the following expressions".into())
+            EvalError::NegativeBaseFractionalExponent { base, exponent, .. } => {
+                write!(f, "Negative base with fractional exponent ({} ^ {})", base, exponent)
             }
-        },
+            EvalError::Overflow { op, lhs, rhs, .. } => {
+                write!(f, "Overflow in {} {} {}", lhs, op, rhs)
+            }
+            EvalError::FactorialOverflow { value, .. } => write!(f, "Overflow in {}!", value),
+            EvalError::NonIntegerBitwiseOperand { op, value, .. } => {
+                write!(f, "Cannot perform bitwise {} on non-integer value {}", op, value)
+            }
+            EvalError::ShiftOutOfRange { op, amount, .. } => {
+                write!(f, "Shift amount {} for {} is out of range", amount, op)
+            }
+            EvalError::NumericOverflow { op, ty, .. } => write!(f, "{} overflow in {}", ty, op),
+            EvalError::InexactResult { op, ty, .. } => {
+                write!(f, "Result of {} is not an exact {}", op, ty)
+            }
+            EvalError::NotRepresentable { value, ty, .. } => {
+                write!(f, "{} is not representable as {}", value, ty)
+            }
+            EvalError::InvalidLiteral { literal, .. } => {
+                write!(f, "Invalid number literal '{}'", literal)
+            }
+            EvalError::TypeMismatch { expected, found, .. } => {
+                write!(f, "Type mismatch: expected {}, found {}", expected, found)
+            }
+            EvalError::UnknownVariable { name, .. } => write!(f, "Unknown variable '{}'", name),
+            EvalError::UnknownFunction { name, .. } => write!(f, "Unknown function '{}'", name),
+            EvalError::UnknownOperator { symbol, .. } => write!(f, "Unknown operator '{}'", symbol),
+            EvalError::AssignToConstant { name, .. } => {
+                write!(f, "Cannot assign to read-only constant '{}'", name)
+            }
+            EvalError::AssignWithoutSession { name, .. } => {
+                write!(f, "Cannot assign to '{}' without a session to store it in", name)
+            }
+            EvalError::ArityMismatch { name, expected, found, .. } => {
+                let expected = match expected {
+                    Arity::Exact(n) => format!("{}", n),
+                    Arity::Range(min, max) => format!("{} to {}", min, max),
+                    Arity::AtLeast(min) => format!("at least {}", min),
+                };
+                write!(f, "Function '{}' expects {} argument(s), got {}", name, expected, found)
+            }
+            EvalError::DomainError { name, args, .. } => {
+                let args: Vec<String> = args.iter().map(f64::to_string).collect();
+                write!(f, "{}({}) is outside the domain of '{}'", name, args.join(", "), name)
+            }
+        }
     }
 }
 
+impl error::Error for EvalError {}
+
 //Unit tests
 #[cfg(test)]
 mod tests {
@@ -228,7 +379,33 @@ mod tests {
 
         ast = Parser::new("1/0").unwrap().parse().unwrap();
         let error = eval(ast);
-        assert!(error.is_err());
+        assert_eq!(error, Err(EvalError::DivisionByZero { dividend: 1.0, span: Span::new(0, 3) }));
+    }
+
+    #[test]
+    fn test_modulo() {
+        use crate::parsemath::parser::Parser;
+
+        let cases = [
+            ("7 % 3", 1.0),
+            ("-7 % 3", 2.0),
+            ("7 % (-3)", -2.0),
+            ("-7 mod 3", 2.0),
+            ("-7 rem 3", -1.0),
+            ("7 rem -3", 1.0),
+            ("7 // 2", 3.0),
+            ("-7 // 2", -4.0),
+            ("5.5 % 2", 1.5),
+        ];
+        for (expr, expected) in cases {
+            let ast = Parser::new(expr).unwrap().parse().unwrap();
+            assert_eq!(eval(ast).unwrap(), expected, "{}", expr);
+        }
+
+        for expr in ["1 % 0", "1 // 0", "1 rem 0", "1 mod (2 - 2)"] {
+            let ast = Parser::new(expr).unwrap().parse().unwrap();
+            assert!(matches!(eval(ast), Err(EvalError::DivisionByZero { .. })), "{}", expr);
+        }
     }
 
     #[test]
@@ -253,7 +430,7 @@ mod tests {
         // Exponent 4
         ast = Parser::new("0^-1").unwrap().parse().unwrap();
         let mut error = eval(ast);
-        assert!(error.is_err());
+        assert!(matches!(error, Err(EvalError::ZeroToNegativePower { exponent, .. }) if exponent == -1.0));
 
         // Exponent 5
         ast = Parser::new("-4^0.5").unwrap().parse().unwrap();
@@ -263,7 +440,7 @@ mod tests {
         // Exponent 6
         ast = Parser::new("100000000^100").unwrap().parse().unwrap();
         error = eval(ast);
-        assert!(error.is_err());
+        assert!(matches!(error, Err(EvalError::Overflow { op: "^", .. })));
         
     }
 
@@ -279,7 +456,7 @@ mod tests {
         // Bitwise And 2
         ast = Parser::new("6.5&2").unwrap().parse().unwrap();
         let error = eval(ast);
-        assert!(error.is_err());
+        assert!(matches!(error, Err(EvalError::NonIntegerBitwiseOperand { op: "&", value, .. }) if value == 6.5));
     }
 
     #[test]
@@ -301,4 +478,173 @@ mod tests {
         let error = eval(ast);
         assert!(error.is_err());
     }
+
+    #[test]
+    fn test_xor_not_shift() {
+        use crate::parsemath::parser::Parser;
+
+        let cases = [
+            ("6 xor 3", 5.0),
+            ("~0", -1.0),
+            ("~5 & 7", 2.0),
+            ("1 << 4", 16.0),
+            ("-16 >> 2", -4.0),
+            ("1 | 2 xor 3 & 4 << 1", 3.0),
+        ];
+        for (expr, expected) in cases {
+            let ast = Parser::new(expr).unwrap().parse().unwrap();
+            assert_eq!(eval(ast).unwrap(), expected, "{}", expr);
+        }
+
+        let mut ast = Parser::new("1 << 64").unwrap().parse().unwrap();
+        assert!(matches!(eval(ast), Err(EvalError::ShiftOutOfRange { amount: 64, .. })));
+
+        ast = Parser::new("1 << 63").unwrap().parse().unwrap();
+        let err = eval(ast).unwrap_err();
+        assert!(matches!(err, EvalError::Overflow { op: "<<", .. }));
+        assert_eq!(err.to_string(), "Overflow in 1 << 63");
+
+        ast = Parser::new("1 >> -1").unwrap().parse().unwrap();
+        let err = eval(ast).unwrap_err();
+        assert!(matches!(err, EvalError::ShiftOutOfRange { amount: -1, .. }));
+        assert_eq!(err.to_string(), "Shift amount -1 for >> is out of range");
+
+        ast = Parser::new("~1.5").unwrap().parse().unwrap();
+        assert!(matches!(eval(ast), Err(EvalError::NonIntegerBitwiseOperand { op: "~", .. })));
+    }
+
+    #[test]
+    fn test_comparison_and_logic() {
+        use crate::parsemath::parser::Parser;
+
+        let cases = [
+            ("1 + 1 == 2", true),
+            ("3 != 3", false),
+            ("2 < 3 && 3 <= 3", true),
+            ("2 > 3 || 4 >= 5", false),
+            ("!(1 > 2)", true),
+            ("true == !false", true),
+            ("(1 < 2) == (2 < 3)", true),
+            ("6 & 3 == 2", true),
+        ];
+        for (expr, expected) in cases {
+            let ast = Parser::new(expr).unwrap().parse().unwrap();
+            assert_eq!(eval(ast).unwrap(), expected, "{}", expr);
+        }
+    }
+
+    #[test]
+    fn test_type_errors() {
+        use crate::parsemath::parser::Parser;
+
+        let mut ast = Parser::new("1 + (2 > 1)").unwrap().parse().unwrap();
+        assert_eq!(
+            eval(ast),
+            Err(EvalError::TypeMismatch { expected: "number", found: "bool", span: Span::new(4, 11) })
+        );
+
+        ast = Parser::new("1 && true").unwrap().parse().unwrap();
+        assert!(matches!(eval(ast), Err(EvalError::TypeMismatch { expected: "bool", .. })));
+
+        ast = Parser::new("true == 1").unwrap().parse().unwrap();
+        assert!(matches!(eval(ast), Err(EvalError::TypeMismatch { .. })));
+
+        // The right side of && is not evaluated once the left side is false
+        ast = Parser::new("false && 1/0 > 1").unwrap().parse().unwrap();
+        assert_eq!(eval(ast).unwrap(), false);
+    }
+
+    #[test]
+    fn test_conditional() {
+        use crate::parsemath::parser::Parser;
+
+        let ast = Parser::new("x != 0 ? 1/x : 0").unwrap().parse().unwrap();
+        assert_eq!(eval_with(&ast, &Context::new().with_var("x", 4.0)).unwrap(), 0.25);
+        // The untaken branch is never evaluated, so 1/0 does not fail
+        assert_eq!(eval_with(&ast, &Context::new().with_var("x", 0.0)).unwrap(), 0.0);
+
+        let ast = Parser::new("if(qty > 10, qty * 0.1, 0)").unwrap().parse().unwrap();
+        assert_eq!(eval_with(&ast, &Context::new().with_var("qty", 20.0)).unwrap(), 2.0);
+        assert_eq!(eval_with(&ast, &Context::new().with_var("qty", 5.0)).unwrap(), 0.0);
+
+        let ast = Parser::new("1 ? 2 : 3").unwrap().parse().unwrap();
+        assert!(matches!(eval(ast), Err(EvalError::TypeMismatch { expected: "bool", .. })));
+    }
+
+    #[test]
+    fn test_integer_literals() {
+        use crate::parsemath::parser::Parser;
+
+        let ast = Parser::new("0xFF & 0b1010 | 0o700").unwrap().parse().unwrap();
+        assert_eq!(eval(ast).unwrap(), 458.0);
+    }
+
+    #[test]
+    fn test_variables() {
+        use crate::parsemath::parser::Parser;
+
+        let ctx = Context::new().with_var("x", 3.0).with_var("rate", 0.5);
+        let ast = Parser::new("2*x + rate").unwrap().parse().unwrap();
+        assert_eq!(eval_with(&ast, &ctx).unwrap(), 6.5);
+
+        // Unbound names are reported with their location
+        let ast = Parser::new("x + y").unwrap().parse().unwrap();
+        assert_eq!(
+            eval_with(&ast, &ctx),
+            Err(EvalError::UnknownVariable { name: "y".into(), span: Span::new(4, 5) })
+        );
+    }
+
+    #[test]
+    fn test_functions() {
+        use crate::parsemath::parser::Parser;
+
+        let mut ast = Parser::new("sqrt(16) + max(1, 7, 3) - abs(-2)").unwrap().parse().unwrap();
+        assert_eq!(eval(ast).unwrap(), 9.0);
+
+        ast = Parser::new("sqrt(-1)").unwrap().parse().unwrap();
+        assert_eq!(
+            eval(ast),
+            Err(EvalError::DomainError { name: "sqrt".into(), args: vec![-1.0], span: Span::new(0, 8) })
+        );
+
+        ast = Parser::new("ln(0)").unwrap().parse().unwrap();
+        assert!(matches!(eval(ast), Err(EvalError::DomainError { .. })));
+
+        ast = Parser::new("sqrt(1, 2)").unwrap().parse().unwrap();
+        assert!(matches!(eval(ast), Err(EvalError::ArityMismatch { found: 2, .. })));
+
+        ast = Parser::new("nope(1)").unwrap().parse().unwrap();
+        assert!(matches!(eval(ast), Err(EvalError::UnknownFunction { .. })));
+    }
+
+    #[test]
+    fn test_constants() {
+        use crate::parsemath::parser::Parser;
+
+        let ctx = Context::new().with_var("r", 2.0);
+        let ast = Parser::new("2*pi*r").unwrap().parse().unwrap();
+        assert_eq!(eval_with(&ast, &ctx).unwrap(), 4.0 * std::f64::consts::PI);
+
+        let ast = Parser::new("tau / 2 - pi").unwrap().parse().unwrap();
+        assert_eq!(eval(ast).unwrap(), 0.0);
+    }
+
+    #[test]
+    fn test_factorial_and_percent() {
+        use crate::parsemath::parser::Parser;
+
+        let ast = Parser::new("-3! + 2^3!").unwrap().parse().unwrap();
+        assert_eq!(eval(ast).unwrap(), 58.0);
+        let ast = Parser::new("200 * 15%").unwrap().parse().unwrap();
+        assert_eq!(eval(ast).unwrap(), 30.0);
+
+        let ast = Parser::new("171!").unwrap().parse().unwrap();
+        assert_eq!(
+            eval(ast),
+            Err(EvalError::FactorialOverflow { value: 171.0, span: Span::new(0, 4) })
+        );
+        let ast = Parser::new("(0 - 2)!").unwrap().parse().unwrap();
+        assert!(matches!(eval(ast), Err(EvalError::DomainError { .. })));
+    }
 }
@@ -0,0 +1,205 @@
+/// This module contains an arbitrary-precision integer type for the `bignum` feature, so that
+/// e.g. 2^521 - 1 or 100! evaluate exactly. Results are capped at a digit limit.
+// Standard lib
+use std::fmt;
+
+// External crates
+use num_bigint::BigInt;
+use num_traits::{Signed, ToPrimitive, Zero};
+
+// Internal modules
+use super::numeric::{self, NumError, Numeric, MAX_EXACT_F64};
+
+pub const DEFAULT_MAX_DIGITS: usize = 10_000;
+
+// Integer of any size up to MAX_DIGITS decimal digits, e.g. evaluate_as::<BigInteger>("100!")
+// or evaluate_as::<BigInteger<500>>(...) for a tighter limit. Results beyond the limit are
+// reported as overflow, so that an expression such as 9^9^9 cannot exhaust memory.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct BigInteger<const MAX_DIGITS: usize = DEFAULT_MAX_DIGITS>(BigInt);
+
+impl<const MAX_DIGITS: usize> BigInteger<MAX_DIGITS> {
+    pub fn new(value: impl Into<BigInt>) -> Self {
+        BigInteger(value.into())
+    }
+
+    pub fn as_bigint(&self) -> &BigInt {
+        &self.0
+    }
+
+    pub fn into_bigint(self) -> BigInt {
+        self.0
+    }
+
+    // Bits needed for MAX_DIGITS decimal digits, log2(10) being just below 3.322
+    fn max_bits() -> u64 {
+        (MAX_DIGITS as u64).saturating_mul(3322) / 1000 + 1
+    }
+
+    // Reject results beyond the digit limit
+    fn checked(value: BigInt) -> Result<Self, NumError> {
+        if value.bits() > Self::max_bits() {
+            return Err(NumError::Overflow);
+        }
+        Ok(BigInteger(value))
+    }
+}
+
+impl<const MAX_DIGITS: usize> fmt::Display for BigInteger<MAX_DIGITS> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl<const MAX_DIGITS: usize> Numeric for BigInteger<MAX_DIGITS> {
+    const NAME: &'static str = "bigint";
+
+    // Beyond 2^53 a float no longer identifies a single integer
+    fn from_f64(value: f64) -> Option<Self> {
+        if value.fract() != 0.0 || value.abs() > MAX_EXACT_F64 {
+            return None;
+        }
+        Self::checked(BigInt::from(value as i64)).ok()
+    }
+
+    // Exact for any literal with an integral value, however many digits it has
+    fn from_literal(literal: &str) -> Option<Self> {
+        let (negative, digits, exponent) = numeric::split_literal(literal)?;
+        let exponent = u32::try_from(exponent).ok()?;
+        if digits.len() as u64 + exponent as u64 > Self::max_bits() {
+            return None;
+        }
+        let value = digits.parse::<BigInt>().ok()? * BigInt::from(10).pow(exponent);
+        Self::checked(if negative { -value } else { value }).ok()
+    }
+
+    fn from_u128(value: u128) -> Option<Self> {
+        Self::checked(BigInt::from(value)).ok()
+    }
+
+    fn to_f64(&self) -> f64 {
+        self.0.to_f64().unwrap_or(f64::NAN)
+    }
+
+    fn is_zero(&self) -> bool {
+        self.0.is_zero()
+    }
+
+    fn add(&self, rhs: &Self) -> Result<Self, NumError> {
+        Self::checked(&self.0 + &rhs.0)
+    }
+
+    fn sub(&self, rhs: &Self) -> Result<Self, NumError> {
+        Self::checked(&self.0 - &rhs.0)
+    }
+
+    fn mul(&self, rhs: &Self) -> Result<Self, NumError> {
+        Self::checked(&self.0 * &rhs.0)
+    }
+
+    fn neg(&self) -> Result<Self, NumError> {
+        Ok(BigInteger(-&self.0))
+    }
+
+    fn div(&self, rhs: &Self) -> Result<Self, NumError> {
+        if !(&self.0 % &rhs.0).is_zero() {
+            return Err(NumError::Inexact);
+        }
+        Ok(BigInteger(&self.0 / &rhs.0))
+    }
+
+    fn rem(&self, rhs: &Self) -> Result<Self, NumError> {
+        Ok(BigInteger(&self.0 % &rhs.0))
+    }
+
+    // The size of the result is estimated before computing it, so 9^9^9 fails immediately
+    fn pow(&self, exponent: &Self) -> Result<Self, NumError> {
+        // 0, 1 and -1 stay small for any exponent
+        let odd = exponent.0.bit(0);
+        match self.0.to_i8() {
+            Some(0) if exponent.0.is_zero() => return Ok(BigInteger(BigInt::from(1))),
+            Some(0) | Some(1) => return Ok(self.clone()),
+            Some(-1) => return Ok(BigInteger(BigInt::from(if odd { -1 } else { 1 }))),
+            _ => {}
+        }
+        if exponent.0.is_negative() {
+            return Err(NumError::Inexact);
+        }
+        let exponent = exponent.0.to_u32().ok_or(NumError::Overflow)?;
+        let min_bits = (self.0.bits() - 1).saturating_mul(exponent as u64);
+        if min_bits > Self::max_bits() {
+            return Err(NumError::Overflow);
+        }
+        Self::checked(self.0.pow(exponent))
+    }
+
+    // Bitwise operators on negative values act on the infinite two's complement representation
+    fn bit_and(&self, rhs: &Self) -> Result<Self, NumError> {
+        Ok(BigInteger(&self.0 & &rhs.0))
+    }
+
+    fn bit_or(&self, rhs: &Self) -> Result<Self, NumError> {
+        Ok(BigInteger(&self.0 | &rhs.0))
+    }
+
+    fn bit_xor(&self, rhs: &Self) -> Result<Self, NumError> {
+        Ok(BigInteger(&self.0 ^ &rhs.0))
+    }
+
+    fn bit_not(&self) -> Result<Self, NumError> {
+        Ok(BigInteger(!&self.0))
+    }
+
+    fn shl(&self, amount: u32) -> Result<Self, NumError> {
+        if !self.0.is_zero() && self.0.bits().saturating_add(amount as u64) > Self::max_bits() {
+            return Err(NumError::Overflow);
+        }
+        Ok(BigInteger(&self.0 << amount))
+    }
+
+    fn shr(&self, amount: u32) -> Result<Self, NumError> {
+        Ok(BigInteger(&self.0 >> amount))
+    }
+}
+
+// Unit tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parsemath::ast::EvalError;
+    use crate::parsemath::numeric::tests;
+
+    fn eval<N: Numeric>(expr: &str) -> Result<String, EvalError> {
+        tests::eval::<N>(expr).map(|value| value.to_string())
+    }
+
+    #[test]
+    fn test_large_values() {
+        let mersenne = "686479766013060971498190079908139321726943530014330540939446345918554318339\
+                        765605212255964066145455497729631139148085803712198799971664381257402829111\
+                        5057151";
+        assert_eq!(eval::<BigInteger>("2^521 - 1").unwrap(), mersenne);
+        assert!(eval::<BigInteger>("100!").unwrap().starts_with("93326215443944152681699238856"));
+        assert_eq!(eval::<BigInteger>("100! / 98!").unwrap(), "9900");
+        let masked = eval::<BigInteger>("(2^100 - 1) & (2^64 + 5) | 2").unwrap();
+        assert_eq!(masked, "18446744073709551623");
+        assert_eq!(eval::<BigInteger>("-(2^70) >> 68").unwrap(), "-4");
+        assert_eq!(eval::<BigInteger>("-7 // 2 + -7 mod 2").unwrap(), "-3");
+    }
+
+    #[test]
+    fn test_errors() {
+        assert!(matches!(
+            eval::<BigInteger>("9^9^9"),
+            Err(EvalError::NumericOverflow { op: "^", ty: "bigint", .. })
+        ));
+        assert!(matches!(
+            eval::<BigInteger<100>>("2^521"),
+            Err(EvalError::NumericOverflow { op: "^", .. })
+        ));
+        assert!(matches!(eval::<BigInteger<10>>("20!"), Err(EvalError::NumericOverflow { .. })));
+        assert!(matches!(eval::<BigInteger>("10^20 / 3"), Err(EvalError::InexactResult { .. })));
+        assert!(matches!(eval::<BigInteger>("2^-3"), Err(EvalError::InexactResult { .. })));
+        assert_eq!(eval::<BigInteger>("(-1)^-3 + 0^0").unwrap(), "0");
+    }
+}
@@ -0,0 +1,13 @@
+/// This module contains the table of built-in named constants, e.g. 2*pi*r.
+// Standard lib
+use std::f64::consts;
+
+// Table of built-in constants, copied into every new evaluation context
+pub const BUILTIN_CONSTANTS: &[(&str, f64)] = &[
+    ("pi", consts::PI),
+    ("e", consts::E),
+    ("tau", consts::TAU),
+    ("phi", 1.618_033_988_749_895), // golden ratio, (1 + sqrt(5)) / 2
+    ("inf", f64::INFINITY),
+    ("nan", f64::NAN),
+];
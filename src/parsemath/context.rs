@@ -0,0 +1,168 @@
+/// This module holds the evaluation context, i.e. the environment that variable,
+/// constant and function names in an expression are resolved against when the AST is evaluated.
+// Standard lib
+use std::collections::HashMap;
+
+// Internal modules
+use super::constants::BUILTIN_CONSTANTS;
+use super::functions::Function;
+use super::operators::{Operator, OperatorTable};
+use super::value::Value;
+
+// Context struct maps variable and constant names to their values, and function names and
+// operator symbols to functions and operators registered by the host application.
+// Variables shadow constants of the same name, unless constants are made read-only.
+#[derive(Debug, Clone)]
+pub struct Context {
+    vars: HashMap<String, Value>,
+    constants: HashMap<String, f64>,
+    read_only_constants: bool,
+    functions: HashMap<String, Function>,
+    operators: OperatorTable,
+}
+
+// A new context starts with the built-in constants (pi, e, tau, phi, inf, nan)
+impl Default for Context {
+    fn default() -> Self {
+        Context {
+            vars: HashMap::new(),
+            constants: BUILTIN_CONSTANTS
+                .iter()
+                .map(|&(name, value)| (name.to_string(), value))
+                .collect(),
+            read_only_constants: false,
+            functions: HashMap::new(),
+            operators: OperatorTable::new(),
+        }
+    }
+}
+
+impl Context {
+    // Create a context with no variables and the built-in constants
+    pub fn new() -> Self {
+        Context::default()
+    }
+
+    // Builder-style helper to bind a variable while constructing a context
+    pub fn with_var(mut self, name: impl Into<String>, value: impl Into<Value>) -> Self {
+        self.set_var(name, value);
+        self
+    }
+
+    // Bind a variable, replacing any previous value
+    pub fn set_var(&mut self, name: impl Into<String>, value: impl Into<Value>) {
+        self.vars.insert(name.into(), value.into());
+    }
+
+    // Look up the value bound to a variable
+    pub fn get_var(&self, name: &str) -> Option<Value> {
+        self.vars.get(name).copied()
+    }
+
+    // Unbind a variable, returning its previous value
+    pub fn remove_var(&mut self, name: &str) -> Option<Value> {
+        self.vars.remove(name)
+    }
+
+    // Builder-style helper to define a constant while constructing a context
+    pub fn with_constant(mut self, name: impl Into<String>, value: f64) -> Self {
+        self.set_constant(name, value);
+        self
+    }
+
+    // Define a new constant or override a built-in one
+    pub fn set_constant(&mut self, name: impl Into<String>, value: f64) {
+        self.constants.insert(name.into(), value);
+    }
+
+    pub fn get_constant(&self, name: &str) -> Option<f64> {
+        self.constants.get(name).copied()
+    }
+
+    // Remove a constant, including a built-in one, returning its previous value
+    pub fn remove_constant(&mut self, name: &str) -> Option<f64> {
+        self.constants.remove(name)
+    }
+
+    // When constants are read-only, variables can no longer shadow them
+    pub fn set_constants_read_only(&mut self, read_only: bool) {
+        self.read_only_constants = read_only;
+    }
+
+    pub fn constants_read_only(&self) -> bool {
+        self.read_only_constants
+    }
+
+    // Resolve a name used in an expression to its value
+    pub fn resolve(&self, name: &str) -> Option<Value> {
+        let constant = self.get_constant(name).map(Value::Number);
+        if self.read_only_constants {
+            constant.or_else(|| self.get_var(name))
+        } else {
+            self.get_var(name).or(constant)
+        }
+    }
+
+    // Register a host function. It takes precedence over a built-in of the same name.
+    pub fn set_fn(&mut self, name: impl Into<String>, function: Function) {
+        self.functions.insert(name.into(), function);
+    }
+
+    // Look up a host function
+    pub fn get_fn(&self, name: &str) -> Option<&Function> {
+        self.functions.get(name)
+    }
+
+    // Register a custom operator, replacing one with the same symbol and fixity
+    pub fn set_operator(&mut self, operator: Operator) {
+        self.operators.insert(operator);
+    }
+
+    // Custom operators, needed to parse expressions that use them
+    pub fn operators(&self) -> &OperatorTable {
+        &self.operators
+    }
+}
+
+// Unit tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_set_and_get() {
+        let mut ctx = Context::new().with_var("x", 2.0);
+        assert_eq!(ctx.get_var("x"), Some(Value::Number(2.0)));
+
+        ctx.set_var("x", true);
+        assert_eq!(ctx.get_var("x"), Some(Value::Bool(true)));
+        assert_eq!(ctx.remove_var("x"), Some(Value::Bool(true)));
+        assert_eq!(ctx.get_var("x"), None);
+    }
+
+    #[test]
+    fn test_constants() {
+        let mut ctx = Context::new().with_constant("g", 9.81);
+        assert_eq!(ctx.resolve("pi"), Some(Value::Number(std::f64::consts::PI)));
+        assert_eq!(ctx.resolve("g"), Some(Value::Number(9.81)));
+        assert_eq!(ctx.get_constant("phi"), Some((1.0 + 5f64.sqrt()) / 2.0));
+        assert!(ctx.get_constant("nan").is_some_and(f64::is_nan));
+
+        // Variables shadow constants unless constants are read-only
+        ctx.set_var("e", 2.0);
+        assert_eq!(ctx.resolve("e"), Some(Value::Number(2.0)));
+        ctx.set_constants_read_only(true);
+        assert_eq!(ctx.resolve("e"), Some(Value::Number(std::f64::consts::E)));
+
+        assert!(ctx.remove_constant("pi").is_some());
+        assert_eq!(ctx.resolve("pi"), None);
+    }
+
+    #[test]
+    fn test_set_fn() {
+        let mut ctx = Context::new();
+        ctx.set_fn("double", Function::new(1, |a| Some(a[0] * 2.0)));
+        assert_eq!(ctx.get_fn("double").unwrap().call(&[4.0]), Some(8.0));
+        assert!(ctx.get_fn("triple").is_none());
+    }
+}
@@ -0,0 +1,540 @@
+/// This module contains a fixed-point decimal number type for financial calculations, so that
+/// 0.1 + 0.2 is exactly 0.3. The scale and the rounding mode are chosen per evaluation.
+// Standard lib
+use std::cmp::Ordering;
+use std::fmt;
+use std::hash::Hash;
+use std::marker::PhantomData;
+
+// Internal modules
+use super::numeric::{self, NumError, Numeric};
+
+pub const DEFAULT_SCALE: u32 = 18;
+
+// How a result with more decimals than the scale is rounded
+pub trait Rounding: fmt::Debug + Copy + Ord + Hash {
+    // Whether to round the magnitude up, given the quotient and remainder of the division
+    // that dropped the extra decimals
+    fn round_up(quotient: u128, remainder: u128, divisor: u128) -> bool;
+}
+
+// Round half to even (banker's rounding): 2.665 -> 2.66, 2.675 -> 2.68
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct HalfEven;
+
+// Round half away from zero: 2.665 -> 2.67, -2.665 -> -2.67
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct HalfUp;
+
+// Drop the extra decimals, i.e. round towards zero: 2.669 -> 2.66
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct Truncate;
+
+impl Rounding for HalfEven {
+    fn round_up(quotient: u128, remainder: u128, divisor: u128) -> bool {
+        let rest = divisor - remainder;
+        remainder > rest || (remainder == rest && quotient % 2 == 1)
+    }
+}
+
+impl Rounding for HalfUp {
+    fn round_up(_: u128, remainder: u128, divisor: u128) -> bool {
+        remainder >= divisor - remainder
+    }
+}
+
+impl Rounding for Truncate {
+    fn round_up(_: u128, _: u128, _: u128) -> bool {
+        false
+    }
+}
+
+// Decimal with SCALE digits after the point, stored as an i128 count of 10^-SCALE units, e.g.
+// evaluate_as::<Decimal<2, HalfUp>>("19.99 * 3"). Literals and results with more decimals
+// are rounded with R. Displays without trailing zeros, or with a fixed number of decimals
+// given a precision, e.g. {:.2}. Conversion to f64 is explicit, see to_f64.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Decimal<const SCALE: u32 = DEFAULT_SCALE, R = HalfEven> {
+    units: i128,
+    rounding: PhantomData<R>,
+}
+
+impl<const SCALE: u32, R: Rounding> Decimal<SCALE, R> {
+    // Value of 1, which fails to compile for a scale above 38
+    const UNIT: u128 = 10u128.pow(SCALE);
+
+    // Decimal from a count of 10^-SCALE units, e.g. Decimal::<2>::from_units(1999) is 19.99
+    pub fn from_units(units: i128) -> Self {
+        Decimal { units, rounding: PhantomData }
+    }
+
+    pub fn units(&self) -> i128 {
+        self.units
+    }
+
+    // Nearest f64, which is no longer exact
+    pub fn to_f64(&self) -> f64 {
+        self.units as f64 / Self::UNIT as f64
+    }
+
+    fn from_integer(value: i128) -> Result<Self, NumError> {
+        value.checked_mul(Self::UNIT as i128).map(Self::from_units).ok_or(NumError::Overflow)
+    }
+
+    // Integer part, rounded towards zero
+    fn integer(&self) -> i128 {
+        self.units / Self::UNIT as i128
+    }
+
+    // Apply the sign to a magnitude rounded according to R
+    fn rounded(negative: bool, quotient: u128, rem: u128, divisor: u128) -> Result<Self, NumError> {
+        let magnitude = match R::round_up(quotient, rem, divisor) {
+            true => quotient.checked_add(1).ok_or(NumError::Overflow)?,
+            false => quotient,
+        };
+        let units = i128::try_from(magnitude).map_err(|_| NumError::Overflow)?;
+        Ok(Self::from_units(if negative { -units } else { units }))
+    }
+
+    // Power with a non-negative integral exponent by repeated squaring, every product rounded
+    fn powi(&self, mut exp: u32) -> Result<Self, NumError> {
+        let (mut base, mut result) = (*self, Self::from_units(Self::UNIT as i128));
+        while exp > 0 {
+            if exp & 1 == 1 {
+                result = result.mul(&base)?;
+            }
+            exp >>= 1;
+            if exp > 0 {
+                base = base.mul(&base)?;
+            }
+        }
+        Ok(result)
+    }
+
+    // Power with a negative exponent -exp, as units 10^SCALE * (den / num)^exp where the value
+    // is num / den in lowest terms. The exact quotient is rounded once.
+    fn recip_powi(&self, exp: u32) -> Result<Self, NumError> {
+        let negative = self.units < 0 && exp % 2 == 1;
+        let magnitude = self.units.unsigned_abs();
+        let divisor = gcd(magnitude, Self::UNIT);
+        let (num, den) = (magnitude / divisor, Self::UNIT / divisor);
+        // Bit lengths bound the size of the quotient before computing any power
+        let bits = |value: u128| u64::from(u128::BITS - value.leading_zeros());
+        let max_dividend_bits = bits(Self::UNIT) + bits(den) * u64::from(exp);
+        if (bits(num) - 1) * u64::from(exp) > max_dividend_bits {
+            // Less than half a unit, but not zero
+            return Self::rounded(negative, 0, 1, 3);
+        }
+        if max_dividend_bits > MAX_WIDE_BITS {
+            return Err(NumError::Overflow);
+        }
+        let dividend = Wide::from_u128(Self::UNIT).mul(&Wide::from_u128(den).pow(exp));
+        let divisor = Wide::from_u128(num).pow(exp);
+        let (quotient, mut remainder) = dividend.div_rem(&divisor).ok_or(NumError::Overflow)?;
+        // Only how the remainder compares to half the divisor matters for rounding
+        let is_zero = remainder.0.is_empty();
+        remainder.shl1();
+        let (rem, div) = match remainder.cmp(&divisor) {
+            Ordering::Less if is_zero => (0, 1),
+            Ordering::Less => (1, 3),
+            Ordering::Equal => (1, 2),
+            Ordering::Greater => (2, 3),
+        };
+        Self::rounded(negative, quotient, rem, div)
+    }
+
+    // a * b / divisor in units, rounded
+    fn mul_div(a: i128, b: i128, divisor: i128) -> Result<Self, NumError> {
+        let negative = ((a < 0) != (b < 0)) != (divisor < 0);
+        let divisor = divisor.unsigned_abs();
+        let (quotient, remainder) =
+            mul_div(a.unsigned_abs(), b.unsigned_abs(), divisor).ok_or(NumError::Overflow)?;
+        Self::rounded(negative, quotient, remainder, divisor)
+    }
+}
+
+// Full 256-bit product of two u128 values, as (high, low) halves
+fn widening_mul(a: u128, b: u128) -> (u128, u128) {
+    const MASK: u128 = u64::MAX as u128;
+    let (a1, a0, b1, b0) = (a >> 64, a & MASK, b >> 64, b & MASK);
+    let (low, cross1, cross2, high) = (a0 * b0, a0 * b1, a1 * b0, a1 * b1);
+    let middle = (low >> 64) + (cross1 & MASK) + (cross2 & MASK);
+    let lo = (low & MASK) | (middle << 64);
+    let hi = high + (cross1 >> 64) + (cross2 >> 64) + (middle >> 64);
+    (hi, lo)
+}
+
+// Quotient and remainder of a * b / divisor, computed without overflowing the intermediate
+// product. None when the quotient does not fit into a u128.
+fn mul_div(a: u128, b: u128, divisor: u128) -> Option<(u128, u128)> {
+    let (hi, lo) = widening_mul(a, b);
+    if hi >= divisor {
+        return None;
+    }
+    // Long division one bit at a time; the remainder stays below the divisor
+    let (mut quotient, mut remainder) = (0u128, hi);
+    for bit in (0..128).rev() {
+        let carry = remainder >> 127;
+        remainder = (remainder << 1) | ((lo >> bit) & 1);
+        quotient <<= 1;
+        if carry == 1 || remainder >= divisor {
+            remainder = remainder.wrapping_sub(divisor);
+            quotient |= 1;
+        }
+    }
+    Some((quotient, remainder))
+}
+
+fn gcd(mut a: u128, mut b: u128) -> u128 {
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a
+}
+
+// Upper bound on the size of the exact powers behind a negative exponent
+const MAX_WIDE_BITS: u64 = 1 << 16;
+
+// Unsigned integer of any size, as little-endian u64 limbs without leading zero limbs.
+// Only used for the exact quotient behind a negative power.
+struct Wide(Vec<u64>);
+
+impl Wide {
+    fn from_u128(value: u128) -> Self {
+        let mut wide = Wide(vec![value as u64, (value >> 64) as u64]);
+        wide.trim();
+        wide
+    }
+
+    fn trim(&mut self) {
+        while self.0.last() == Some(&0) {
+            self.0.pop();
+        }
+    }
+
+    fn bits(&self) -> u64 {
+        match self.0.last() {
+            Some(top) => self.0.len() as u64 * 64 - u64::from(top.leading_zeros()),
+            None => 0,
+        }
+    }
+
+    fn bit(&self, index: u64) -> bool {
+        let limb = self.0.get((index / 64) as usize).copied().unwrap_or(0);
+        (limb >> (index % 64)) & 1 == 1
+    }
+
+    fn mul(&self, rhs: &Wide) -> Wide {
+        let mut limbs = vec![0u64; self.0.len() + rhs.0.len()];
+        for (i, &a) in self.0.iter().enumerate() {
+            let mut carry = 0u128;
+            for (j, &b) in rhs.0.iter().enumerate() {
+                let product = a as u128 * b as u128 + limbs[i + j] as u128 + carry;
+                limbs[i + j] = product as u64;
+                carry = product >> 64;
+            }
+            limbs[i + rhs.0.len()] = carry as u64;
+        }
+        let mut wide = Wide(limbs);
+        wide.trim();
+        wide
+    }
+
+    fn pow(&self, mut exp: u32) -> Wide {
+        let (mut base, mut result) = (Wide(self.0.clone()), Wide::from_u128(1));
+        while exp > 0 {
+            if exp & 1 == 1 {
+                result = result.mul(&base);
+            }
+            exp >>= 1;
+            if exp > 0 {
+                base = base.mul(&base);
+            }
+        }
+        result
+    }
+
+    fn shl1(&mut self) {
+        let mut carry = 0;
+        for limb in self.0.iter_mut() {
+            (*limb, carry) = ((*limb << 1) | carry, *limb >> 63);
+        }
+        if carry != 0 {
+            self.0.push(carry);
+        }
+    }
+
+    // Subtract a smaller or equal value
+    fn sub_assign(&mut self, rhs: &Wide) {
+        let mut borrow = false;
+        for (i, limb) in self.0.iter_mut().enumerate() {
+            let (diff, borrow1) = limb.overflowing_sub(rhs.0.get(i).copied().unwrap_or(0));
+            let (diff, borrow2) = diff.overflowing_sub(u64::from(borrow));
+            (*limb, borrow) = (diff, borrow1 || borrow2);
+        }
+        self.trim();
+    }
+
+    fn cmp(&self, rhs: &Wide) -> Ordering {
+        self.0.len().cmp(&rhs.0.len()).then_with(|| self.0.iter().rev().cmp(rhs.0.iter().rev()))
+    }
+
+    // Quotient and remainder by long division one bit at a time. None when the quotient does
+    // not fit into a u128.
+    fn div_rem(&self, divisor: &Wide) -> Option<(u128, Wide)> {
+        let (mut quotient, mut remainder) = (0u128, Wide(Vec::new()));
+        for index in (0..self.bits()).rev() {
+            remainder.shl1();
+            if self.bit(index) {
+                match remainder.0.first_mut() {
+                    Some(low) => *low |= 1,
+                    None => remainder.0.push(1),
+                }
+            }
+            if remainder.cmp(divisor) != Ordering::Less {
+                if index >= 128 {
+                    return None;
+                }
+                remainder.sub_assign(divisor);
+                quotient |= 1 << index;
+            }
+        }
+        Some((quotient, remainder))
+    }
+}
+
+impl<const SCALE: u32, R: Rounding> fmt::Display for Decimal<SCALE, R> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let magnitude = self.units.unsigned_abs();
+        // Round to the requested precision first, e.g. 2.675 with {:.2}
+        let (magnitude, scale) = match f.precision() {
+            Some(precision) if (precision as u32) < SCALE => {
+                let divisor = 10u128.pow(SCALE - precision as u32);
+                let (quotient, remainder) = (magnitude / divisor, magnitude % divisor);
+                (quotient + R::round_up(quotient, remainder, divisor) as u128, precision as u32)
+            }
+            _ => (magnitude, SCALE),
+        };
+        let unit = 10u128.pow(scale);
+        let (int, frac) = (magnitude / unit, magnitude % unit);
+        let mut frac = match scale {
+            0 => String::new(),
+            _ => format!("{:0width$}", frac, width = scale as usize),
+        };
+        match f.precision() {
+            Some(precision) => frac.extend(std::iter::repeat_n('0', precision - frac.len())),
+            None => frac.truncate(frac.trim_end_matches('0').len()),
+        }
+        let sign = if self.units < 0 && magnitude != 0 { "-" } else { "" };
+        if frac.is_empty() {
+            return write!(f, "{}{}", sign, int);
+        }
+        write!(f, "{}{}.{}", sign, int, frac)
+    }
+}
+
+impl<const SCALE: u32, R: Rounding> Numeric for Decimal<SCALE, R> {
+    const NAME: &'static str = "decimal";
+
+    // Goes through the shortest decimal representation, so that 0.1 stays 0.1
+    fn from_f64(value: f64) -> Option<Self> {
+        if !value.is_finite() {
+            return None;
+        }
+        Self::from_literal(&value.to_string())
+    }
+
+    fn from_u128(value: u128) -> Option<Self> {
+        let units = value.checked_mul(Self::UNIT)?;
+        i128::try_from(units).ok().map(Self::from_units)
+    }
+
+    // Digits beyond the scale are rounded, e.g. 2.675 is 2.68 with a scale of 2
+    fn from_literal(literal: &str) -> Option<Self> {
+        let (negative, digits, exponent) = numeric::split_literal(literal)?;
+        let shift = exponent.checked_add(SCALE as i32)?;
+        if shift >= 0 {
+            let scale = 10u128.checked_pow(shift as u32)?;
+            let units = digits.parse::<u128>().ok()?.checked_mul(scale)?;
+            return Self::rounded(negative, units, 0, 1).ok();
+        }
+        // The last `dropped` digits, possibly preceded by zeros, are below a unit
+        let dropped = shift.unsigned_abs() as usize;
+        let (kept, rest) = digits.split_at(digits.len().saturating_sub(dropped));
+        let quotient = if kept.is_empty() { 0 } else { kept.parse::<u128>().ok()? };
+        let (remainder, divisor) = match dropped {
+            ..=38 => (rest.parse::<u128>().ok()?, 10u128.pow(dropped as u32)),
+            // Too long for a u128: the first 37 digits decide the rounding, and the rest is
+            // not zero, as the digits have no trailing zeros
+            _ => {
+                let zeros = std::iter::repeat_n('0', dropped - rest.len());
+                let leading: String = zeros.chain(rest.chars()).take(37).collect();
+                (format!("{}1", leading).parse::<u128>().ok()?, 10u128.pow(38))
+            }
+        };
+        Self::rounded(negative, quotient, remainder, divisor).ok()
+    }
+
+    fn to_f64(&self) -> f64 {
+        Decimal::to_f64(self)
+    }
+
+    fn is_zero(&self) -> bool {
+        self.units == 0
+    }
+
+    fn add(&self, rhs: &Self) -> Result<Self, NumError> {
+        self.units.checked_add(rhs.units).map(Self::from_units).ok_or(NumError::Overflow)
+    }
+
+    fn sub(&self, rhs: &Self) -> Result<Self, NumError> {
+        self.units.checked_sub(rhs.units).map(Self::from_units).ok_or(NumError::Overflow)
+    }
+
+    fn mul(&self, rhs: &Self) -> Result<Self, NumError> {
+        Self::mul_div(self.units, rhs.units, Self::UNIT as i128)
+    }
+
+    fn neg(&self) -> Result<Self, NumError> {
+        self.units.checked_neg().map(Self::from_units).ok_or(NumError::Overflow)
+    }
+
+    // Rounded to the scale rather than reported as inexact, e.g. 10 / 3 is 3.33 for a scale of 2
+    fn div(&self, rhs: &Self) -> Result<Self, NumError> {
+        Self::mul_div(self.units, Self::UNIT as i128, rhs.units)
+    }
+
+    fn rem(&self, rhs: &Self) -> Result<Self, NumError> {
+        Ok(Self::from_units(self.units.wrapping_rem(rhs.units)))
+    }
+
+    // Integral exponents only. Positive powers round every product to the scale, negative
+    // powers are computed exactly and rounded once.
+    fn pow(&self, exponent: &Self) -> Result<Self, NumError> {
+        if exponent.units % Self::UNIT as i128 != 0 {
+            return Err(NumError::Inexact);
+        }
+        let exp = exponent.integer();
+        let one = Self::from_units(Self::UNIT as i128);
+        match self.units.unsigned_abs() {
+            0 => return Ok(if exp == 0 { one } else { *self }),
+            unit if unit == Self::UNIT => {
+                return Ok(if self.units < 0 && exp % 2 != 0 { *self } else { one })
+            }
+            _ => {}
+        }
+        let exp_abs = u32::try_from(exp.unsigned_abs()).map_err(|_| NumError::Overflow)?;
+        if exp >= 0 {
+            return self.powi(exp_abs);
+        }
+        self.recip_powi(exp_abs)
+    }
+
+    // Integral operands have no decimals, so their integer parts are combined as i128
+    fn bit_and(&self, rhs: &Self) -> Result<Self, NumError> {
+        Self::from_integer(self.integer().bit_and(&rhs.integer())?)
+    }
+
+    fn bit_or(&self, rhs: &Self) -> Result<Self, NumError> {
+        Self::from_integer(self.integer().bit_or(&rhs.integer())?)
+    }
+
+    fn bit_xor(&self, rhs: &Self) -> Result<Self, NumError> {
+        Self::from_integer(self.integer().bit_xor(&rhs.integer())?)
+    }
+
+    fn bit_not(&self) -> Result<Self, NumError> {
+        Self::from_integer(self.integer().bit_not()?)
+    }
+
+    fn shl(&self, amount: u32) -> Result<Self, NumError> {
+        Self::from_integer(self.integer().shl(amount)?)
+    }
+
+    fn shr(&self, amount: u32) -> Result<Self, NumError> {
+        Self::from_integer(self.integer().shr(amount)?)
+    }
+}
+
+// Unit tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dec<const SCALE: u32, R: Rounding>(literal: &str) -> Decimal<SCALE, R> {
+        Decimal::from_literal(literal).unwrap()
+    }
+
+    #[test]
+    fn test_literals() {
+        assert_eq!(dec::<2, HalfEven>("2.665").units(), 266);
+        assert_eq!(dec::<2, HalfEven>("2.675").units(), 268);
+        assert_eq!(dec::<2, HalfUp>("2.665").units(), 267);
+        assert_eq!(dec::<2, HalfUp>("-2.665").units(), -267);
+        assert_eq!(dec::<2, Truncate>("2.669").units(), 266);
+        assert_eq!(dec::<2, HalfEven>("1.5e2").units(), 15000);
+        assert_eq!(dec::<4, HalfEven>("1e-60").units(), 0);
+        assert_eq!(Decimal::<2>::from_f64(0.1), Some(Decimal::from_units(10)));
+        assert_eq!(Decimal::<2>::from_literal("1e40"), None);
+        // More significant digits than an i128 holds are rounded away
+        let long = "1.234567890123456789012345678901234567890123456789";
+        assert_eq!(dec::<18, HalfEven>(long).units(), 1_234_567_890_123_456_789);
+        let above_half = "0.125000000000000000000000000000000000000000001";
+        assert_eq!(dec::<2, HalfEven>(above_half).units(), 13);
+        assert_eq!(dec::<2, HalfEven>("0.125").units(), 12);
+        assert_eq!(dec::<4, HalfUp>("5e-5").units(), 1);
+        let tiny = "9.99999999999999999999999999999999999999999e-41";
+        assert_eq!(dec::<4, Truncate>(tiny).units(), 0);
+    }
+
+    #[test]
+    fn test_arithmetic() {
+        let (a, b) = (dec::<18, HalfEven>("0.1"), dec::<18, HalfEven>("0.2"));
+        assert_eq!(a.add(&b), Ok(dec("0.3")));
+        assert_eq!(dec::<2, HalfUp>("1.25").mul(&dec("1.25")), Ok(dec("1.56")));
+        assert_eq!(dec::<2, HalfEven>("1.25").mul(&dec("1.25")), Ok(dec("1.56")));
+        assert_eq!(dec::<2, HalfEven>("10").div(&dec("3")), Ok(dec("3.33")));
+        assert_eq!(dec::<2, HalfUp>("-20").div(&dec("3")), Ok(dec("-6.67")));
+        assert_eq!(dec::<2, Truncate>("-20").div(&dec("3")), Ok(dec("-6.66")));
+        assert_eq!(dec::<2, HalfEven>("-7.5").floor_div(&dec("2")), Ok(dec("-4")));
+        assert_eq!(dec::<2, HalfEven>("-7.5").modulo(&dec("2")), Ok(dec("0.5")));
+        assert_eq!(dec::<4, HalfEven>("1.1").pow(&dec("2")), Ok(dec("1.21")));
+        assert_eq!(dec::<4, HalfEven>("2").pow(&dec("-2")), Ok(dec("0.25")));
+        assert_eq!(dec::<4, HalfEven>("2").pow(&dec("0.5")), Err(NumError::Inexact));
+        // Negative powers are exact before rounding once
+        assert_eq!(dec::<2, HalfEven>("0.5").pow(&dec("-3")), Ok(dec("8")));
+        assert_eq!(dec::<2, HalfEven>("0.15").pow(&dec("-2")), Ok(dec("44.44")));
+        assert_eq!(dec::<2, HalfEven>("0.15").pow(&dec("-3")), Ok(dec("296.30")));
+        assert_eq!(dec::<2, HalfUp>("-0.15").pow(&dec("-3")), Ok(dec("-296.30")));
+        assert_eq!(dec::<4, HalfEven>("0.0003").pow(&dec("-2")), Ok(dec("11111111.1111")));
+        assert_eq!(dec::<18, HalfEven>("0.5").pow(&dec("-100")), Err(NumError::Overflow));
+        let almost_one = dec::<18, HalfEven>("0.999999999999999999");
+        assert_eq!(almost_one.pow(&dec("-2")), Ok(dec("1.000000000000000002")));
+        // The power itself overflows, but its reciprocal rounds to 0
+        assert_eq!(dec::<18, HalfEven>("10").pow(&dec("-40")), Ok(dec("0")));
+        assert_eq!(dec::<30, HalfEven>("10").pow(&dec("-12")), Ok(dec("1e-12")));
+        assert_eq!(dec::<4, HalfEven>("0.1").pow(&dec("-40")), Err(NumError::Overflow));
+        // The intermediate product of the units exceeds i128
+        let big = dec::<18, HalfEven>("1e15");
+        assert_eq!(big.mul(&dec("1e-10")), Ok(dec("100000")));
+        assert_eq!(big.mul(&big), Err(NumError::Overflow));
+    }
+
+    #[test]
+    fn test_display() {
+        assert_eq!(dec::<18, HalfEven>("0.30").to_string(), "0.3");
+        assert_eq!(dec::<18, HalfEven>("-12").to_string(), "-12");
+        assert_eq!(format!("{:.2}", dec::<4, HalfEven>("2.675")), "2.68");
+        assert_eq!(format!("{:.2}", dec::<4, Truncate>("-2.679")), "-2.67");
+        assert_eq!(format!("{:.3}", dec::<2, HalfEven>("1.5")), "1.500");
+        assert_eq!(format!("{:.0}", dec::<2, HalfEven>("-0.4")), "0");
+    }
+
+    #[test]
+    fn test_mul_div() {
+        assert_eq!(mul_div(u128::MAX, u128::MAX, u128::MAX), Some((u128::MAX, 0)));
+        assert_eq!(mul_div(u128::MAX, 2, 3), Some((u128::MAX / 3 * 2, 0)));
+        assert_eq!(mul_div(u128::MAX, 2, 1), None);
+        assert_eq!(mul_div(10, 10, 7), Some((14, 2)));
+    }
+}
@@ -0,0 +1,157 @@
+/// This module implements the Numeric trait for f64, the default number type, so that the
+/// default evaluator is the generic one. Only the operations that always were errors fail.
+// Internal modules
+use super::ast::EvalError;
+use super::functions;
+use super::numeric::{NumError, Numeric};
+use super::span::Span;
+
+impl Numeric for f64 {
+    const NAME: &'static str = "f64";
+
+    fn from_f64(value: f64) -> Option<Self> {
+        Some(value)
+    }
+
+    // Literals beyond the range of f64 are not representable
+    fn from_literal(literal: &str) -> Option<Self> {
+        literal.parse().ok().filter(|value: &f64| value.is_finite())
+    }
+
+    fn from_u128(value: u128) -> Option<Self> {
+        Some(value as f64)
+    }
+
+    fn to_f64(&self) -> f64 {
+        *self
+    }
+
+    // Divisors this close to zero are treated as zero
+    fn is_zero(&self) -> bool {
+        self.abs() < f64::EPSILON
+    }
+
+    // Bitwise operators only accept values that are exactly representable as i64
+    fn is_integer(&self) -> bool {
+        self.fract() == 0.0 && *self >= (i64::MIN as f64) && *self <= (i64::MAX as f64)
+    }
+
+    fn add(&self, rhs: &Self) -> Result<Self, NumError> {
+        Ok(self + rhs)
+    }
+
+    fn sub(&self, rhs: &Self) -> Result<Self, NumError> {
+        Ok(self - rhs)
+    }
+
+    fn mul(&self, rhs: &Self) -> Result<Self, NumError> {
+        Ok(self * rhs)
+    }
+
+    fn neg(&self) -> Result<Self, NumError> {
+        Ok(-self)
+    }
+
+    fn div(&self, rhs: &Self) -> Result<Self, NumError> {
+        Ok(self / rhs)
+    }
+
+    // Rounding the quotient directly, as (self - rem) / rhs need not be exact for floats
+    fn floor_div(&self, rhs: &Self) -> Result<Self, NumError> {
+        Ok((self / rhs).floor())
+    }
+
+    fn rem(&self, rhs: &Self) -> Result<Self, NumError> {
+        Ok(self % rhs)
+    }
+
+    // A negative base only has a real power for an integral exponent
+    fn pow(&self, exponent: &Self) -> Result<Self, NumError> {
+        if *self < 0.0 && exponent.fract().abs() > f64::EPSILON {
+            return Err(NumError::Undefined);
+        }
+        let res = self.powf(*exponent);
+        if res.is_infinite() {
+            return Err(NumError::Overflow);
+        }
+        Ok(res)
+    }
+
+    // Non-integers go through the gamma function, e.g. 0.5! = sqrt(pi) / 2
+    fn factorial(&self) -> Result<Self, NumError> {
+        let res = functions::factorial(*self).ok_or(NumError::Undefined)?;
+        if res.is_infinite() {
+            return Err(NumError::Overflow);
+        }
+        Ok(res)
+    }
+
+    fn bit_and(&self, rhs: &Self) -> Result<Self, NumError> {
+        Ok(((*self as i64) & (*rhs as i64)) as f64)
+    }
+
+    fn bit_or(&self, rhs: &Self) -> Result<Self, NumError> {
+        Ok(((*self as i64) | (*rhs as i64)) as f64)
+    }
+
+    fn bit_xor(&self, rhs: &Self) -> Result<Self, NumError> {
+        Ok(((*self as i64) ^ (*rhs as i64)) as f64)
+    }
+
+    fn bit_not(&self) -> Result<Self, NumError> {
+        Ok(!(*self as i64) as f64)
+    }
+
+    // Shift amounts must be in the range 0..64, and shifting out set bits or into the sign
+    // bit overflows
+    fn shl(&self, amount: u32) -> Result<Self, NumError> {
+        let value = *self as i64;
+        let shifted = value.checked_shl(amount).ok_or(NumError::Overflow)?;
+        if shifted >> amount != value {
+            return Err(NumError::Overflow);
+        }
+        Ok(shifted as f64)
+    }
+
+    fn shr(&self, amount: u32) -> Result<Self, NumError> {
+        let value = (*self as i64).checked_shr(amount).ok_or(NumError::Overflow)?;
+        Ok(value as f64)
+    }
+
+    // Keep the specific errors f64 evaluation has always reported
+    fn overflow_error(op: &'static str, operands: &[Self], span: Span) -> EvalError {
+        match (op, operands) {
+            ("!", &[value]) => EvalError::FactorialOverflow { value, span },
+            ("<<", &[lhs, rhs]) if rhs < 64.0 => EvalError::Overflow { op, lhs, rhs, span },
+            ("<<" | ">>", &[_, amount]) => {
+                EvalError::ShiftOutOfRange { op, amount: amount as i64, span }
+            }
+            (_, &[lhs, rhs]) => EvalError::Overflow { op, lhs, rhs, span },
+            _ => EvalError::NumericOverflow { op, ty: Self::NAME, span },
+        }
+    }
+}
+
+// Unit tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_domain_checks() {
+        assert!(1e-17.is_zero());
+        assert!(!0.1.is_integer());
+        assert!(!1e19.is_integer());
+        assert_eq!(Numeric::pow(&-8.0, &(1.0 / 3.0)), Err(NumError::Undefined));
+        assert_eq!(Numeric::pow(&-2.0, &3.0), Ok(-8.0));
+        assert_eq!(Numeric::pow(&10.0, &400.0), Err(NumError::Overflow));
+        assert_eq!((-1.0).factorial(), Err(NumError::Undefined));
+        assert_eq!(171.0.factorial(), Err(NumError::Overflow));
+        assert_eq!(1.0.shl(64), Err(NumError::Overflow));
+        assert_eq!(1.0.shl(63), Err(NumError::Overflow));
+        assert_eq!((-1.0).shl(63), Ok(i64::MIN as f64));
+        assert_eq!(3.0.shl(62), Err(NumError::Overflow));
+        assert_eq!(f64::from_literal("1e400"), None);
+        assert_eq!((-7.0).modulo(&2.0), Ok(1.0));
+    }
+}
@@ -0,0 +1,245 @@
+/// This module contains the standard library of built-in functions that can be called
+/// from an expression, e.g. sqrt(2) or max(1, x, 3), and the type used for functions
+/// registered by the host application.
+// Standard lib
+use std::fmt;
+use std::sync::Arc;
+
+// Number of arguments a function accepts
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Arity {
+    Exact(usize),
+    Range(usize, usize),
+    AtLeast(usize),
+}
+
+impl Arity {
+    pub fn accepts(&self, count: usize) -> bool {
+        match *self {
+            Arity::Exact(n) => count == n,
+            Arity::Range(min, max) => (min..=max).contains(&count),
+            Arity::AtLeast(min) => count >= min,
+        }
+    }
+}
+
+// A plain number is an exact argument count, e.g. register_fn("tax", 1, ...)
+impl From<usize> for Arity {
+    fn from(count: usize) -> Self {
+        Arity::Exact(count)
+    }
+}
+
+// A built-in function returns None when its arguments are outside of its domain
+pub struct Builtin {
+    pub name: &'static str,
+    pub arity: Arity,
+    pub func: fn(&[f64]) -> Option<f64>,
+}
+
+// Table of built-in functions, looked up by name at evaluation time
+const BUILTINS: &[Builtin] = &[
+    Builtin { name: "sin", arity: Arity::Exact(1), func: |a| Some(a[0].sin()) },
+    Builtin { name: "cos", arity: Arity::Exact(1), func: |a| Some(a[0].cos()) },
+    Builtin { name: "tan", arity: Arity::Exact(1), func: |a| Some(a[0].tan()) },
+    Builtin { name: "asin", arity: Arity::Exact(1), func: |a| within(a[0].asin(), a[0].abs() <= 1.0) },
+    Builtin { name: "acos", arity: Arity::Exact(1), func: |a| within(a[0].acos(), a[0].abs() <= 1.0) },
+    Builtin { name: "atan", arity: Arity::Exact(1), func: |a| Some(a[0].atan()) },
+    Builtin { name: "atan2", arity: Arity::Exact(2), func: |a| Some(a[0].atan2(a[1])) },
+    Builtin { name: "sinh", arity: Arity::Exact(1), func: |a| Some(a[0].sinh()) },
+    Builtin { name: "cosh", arity: Arity::Exact(1), func: |a| Some(a[0].cosh()) },
+    Builtin { name: "tanh", arity: Arity::Exact(1), func: |a| Some(a[0].tanh()) },
+    Builtin { name: "sqrt", arity: Arity::Exact(1), func: |a| within(a[0].sqrt(), a[0] >= 0.0) },
+    Builtin { name: "cbrt", arity: Arity::Exact(1), func: |a| Some(a[0].cbrt()) },
+    Builtin { name: "exp", arity: Arity::Exact(1), func: |a| Some(a[0].exp()) },
+    Builtin { name: "ln", arity: Arity::Exact(1), func: |a| within(a[0].ln(), a[0] > 0.0) },
+    Builtin { name: "log", arity: Arity::Range(1, 2), func: log },
+    Builtin { name: "log2", arity: Arity::Exact(1), func: |a| within(a[0].log2(), a[0] > 0.0) },
+    Builtin { name: "log10", arity: Arity::Exact(1), func: |a| within(a[0].log10(), a[0] > 0.0) },
+    Builtin { name: "abs", arity: Arity::Exact(1), func: |a| Some(a[0].abs()) },
+    Builtin { name: "sign", arity: Arity::Exact(1), func: |a| Some(sign(a[0])) },
+    Builtin { name: "floor", arity: Arity::Exact(1), func: |a| Some(a[0].floor()) },
+    Builtin { name: "ceil", arity: Arity::Exact(1), func: |a| Some(a[0].ceil()) },
+    Builtin { name: "round", arity: Arity::Exact(1), func: |a| Some(a[0].round()) },
+    Builtin { name: "trunc", arity: Arity::Exact(1), func: |a| Some(a[0].trunc()) },
+    Builtin { name: "gamma", arity: Arity::Exact(1), func: |a| gamma(a[0]) },
+    Builtin { name: "hypot", arity: Arity::Exact(2), func: |a| Some(a[0].hypot(a[1])) },
+    Builtin { name: "min", arity: Arity::AtLeast(1), func: |a| a.iter().copied().reduce(f64::min) },
+    Builtin { name: "max", arity: Arity::AtLeast(1), func: |a| a.iter().copied().reduce(f64::max) },
+];
+
+// Signature shared by built-in and host functions
+pub type NativeFn = dyn Fn(&[f64]) -> Option<f64> + Send + Sync;
+
+// Function registered by the host application. Like the built-ins, the closure
+// returns None when its arguments are outside of its domain.
+#[derive(Clone)]
+pub struct Function {
+    pub arity: Arity,
+    pub(crate) func: Arc<NativeFn>,
+}
+
+impl Function {
+    pub fn new<F>(arity: impl Into<Arity>, func: F) -> Self
+    where
+        F: Fn(&[f64]) -> Option<f64> + Send + Sync + 'static,
+    {
+        Function {
+            arity: arity.into(),
+            func: Arc::new(func),
+        }
+    }
+
+    pub fn call(&self, args: &[f64]) -> Option<f64> {
+        (self.func)(args)
+    }
+}
+
+impl fmt::Debug for Function {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Function").field("arity", &self.arity).finish_non_exhaustive()
+    }
+}
+
+// Find a built-in function by name
+pub fn lookup(name: &str) -> Option<&'static Builtin> {
+    BUILTINS.iter().find(|builtin| builtin.name == name)
+}
+
+// Keep a result only if the arguments satisfied the function's domain
+fn within(value: f64, in_domain: bool) -> Option<f64> {
+    if in_domain {
+        Some(value)
+    } else {
+        None
+    }
+}
+
+// log(x) is the base-10 logarithm, log(x, b) the logarithm in base b
+fn log(args: &[f64]) -> Option<f64> {
+    match args.get(1) {
+        None => within(args[0].log10(), args[0] > 0.0),
+        Some(&base) => within(args[0].log(base), args[0] > 0.0 && base > 0.0 && base != 1.0),
+    }
+}
+
+// n! for non-negative integers, and gamma(n + 1) for other numbers.
+// Negative integers are outside of the domain. Overflow gives infinity.
+pub fn factorial(n: f64) -> Option<f64> {
+    if n.fract() != 0.0 || n.is_nan() {
+        return gamma(n + 1.0);
+    }
+    if n < 0.0 {
+        return None;
+    }
+    // 170! is the largest factorial that fits into an f64
+    if n > 170.0 {
+        return Some(f64::INFINITY);
+    }
+    Some((2..=n as u32).fold(1.0, |acc, k| acc * k as f64))
+}
+
+// Coefficients of the Lanczos approximation with g = 7
+const LANCZOS: [f64; 9] = [
+    0.999_999_999_999_809_9,
+    676.520_368_121_885_1,
+    -1_259.139_216_722_402_8,
+    771.323_428_777_653_1,
+    -176.615_029_162_140_6,
+    12.507_343_278_686_905,
+    -0.138_571_095_265_720_12,
+    9.984_369_578_019_572e-6,
+    1.505_632_735_149_311_6e-7,
+];
+
+// Gamma function, undefined at zero and the negative integers
+fn gamma(x: f64) -> Option<f64> {
+    if x <= 0.0 && x.fract() == 0.0 {
+        return None;
+    }
+    if x < 0.5 {
+        // Reflection formula
+        let pi = std::f64::consts::PI;
+        return gamma(1.0 - x).map(|g| pi / ((pi * x).sin() * g));
+    }
+    if x > 171.7 {
+        return Some(f64::INFINITY);
+    }
+    let x = x - 1.0;
+    let t = x + 7.5;
+    let sum = (1..LANCZOS.len()).fold(LANCZOS[0], |acc, i| acc + LANCZOS[i] / (x + i as f64));
+    // t^(x + 0.5) is split in two halves so it does not overflow before e^-t scales it down
+    let half = t.powf((x + 0.5) / 2.0);
+    Some((2.0 * std::f64::consts::PI).sqrt() * half * (-t).exp() * half * sum)
+}
+
+fn sign(x: f64) -> f64 {
+    if x == 0.0 {
+        0.0
+    } else {
+        x.signum()
+    }
+}
+
+// Unit tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn call(name: &str, args: &[f64]) -> Option<f64> {
+        (lookup(name).unwrap().func)(args)
+    }
+
+    #[test]
+    fn test_arity() {
+        assert!(Arity::Exact(1).accepts(1));
+        assert!(!Arity::Exact(1).accepts(2));
+        assert!(Arity::Range(1, 2).accepts(2));
+        assert!(!Arity::Range(1, 2).accepts(0));
+        assert!(Arity::AtLeast(1).accepts(5));
+        assert!(!Arity::AtLeast(1).accepts(0));
+    }
+
+    #[test]
+    fn test_builtins() {
+        assert_eq!(call("sqrt", &[16.0]), Some(4.0));
+        assert_eq!(call("log", &[1000.0]), Some(3.0));
+        assert_eq!(call("log", &[8.0, 2.0]), Some(3.0));
+        assert_eq!(call("max", &[1.0, 7.0, 3.0]), Some(7.0));
+        assert_eq!(call("min", &[1.0, 7.0, -3.0]), Some(-3.0));
+        assert_eq!(call("sign", &[-0.0]), Some(0.0));
+        assert!(lookup("nope").is_none());
+    }
+
+    #[test]
+    fn test_host_function() {
+        let tax = Function::new(1, |a| Some(a[0] * 1.2));
+        assert_eq!(tax.arity, Arity::Exact(1));
+        assert_eq!(tax.call(&[10.0]), Some(12.0));
+    }
+
+    #[test]
+    fn test_domain_errors() {
+        assert_eq!(call("sqrt", &[-1.0]), None);
+        assert_eq!(call("ln", &[0.0]), None);
+        assert_eq!(call("asin", &[2.0]), None);
+        assert_eq!(call("log", &[8.0, 1.0]), None);
+        assert_eq!(call("gamma", &[-2.0]), None);
+    }
+
+    #[test]
+    fn test_factorial_and_gamma() {
+        assert_eq!(factorial(0.0), Some(1.0));
+        assert_eq!(factorial(5.0), Some(120.0));
+        assert_eq!(factorial(170.0).map(f64::is_finite), Some(true));
+        assert_eq!(factorial(171.0), Some(f64::INFINITY));
+        assert_eq!(factorial(-1.0), None);
+
+        let close = |a: Option<f64>, b: f64| (a.unwrap() - b).abs() < 1e-9 * b.abs().max(1.0);
+        assert!(close(call("gamma", &[5.0]), 24.0));
+        assert!(close(call("gamma", &[0.5]), std::f64::consts::PI.sqrt()));
+        assert!(close(call("gamma", &[-0.5]), -2.0 * std::f64::consts::PI.sqrt()));
+        assert!(close(factorial(0.5), std::f64::consts::PI.sqrt() / 2.0));
+        assert!(close(call("gamma", &[171.0]), factorial(170.0).unwrap()));
+    }
+}
@@ -0,0 +1,167 @@
+/// This module implements the Numeric trait for i64 and i128, so that expressions can be
+/// evaluated exactly with machine integers, with overflow reported instead of wrapping.
+// Internal modules
+use super::numeric::{self, NumError, Numeric, MAX_EXACT_F64};
+
+macro_rules! impl_numeric_integer {
+    ($($t:ty),*) => {$(
+        impl Numeric for $t {
+            const NAME: &'static str = stringify!($t);
+
+            // Beyond 2^53 a float no longer identifies a single integer
+            fn from_f64(value: f64) -> Option<Self> {
+                (value.fract() == 0.0 && value.abs() <= MAX_EXACT_F64).then_some(value as $t)
+            }
+
+            fn from_u128(value: u128) -> Option<Self> {
+                <$t>::try_from(value).ok()
+            }
+
+            // Exact for any literal with an integral value, e.g. 12345678901234567890 or 2.5e3
+            fn from_literal(literal: &str) -> Option<Self> {
+                let (negative, digits, exponent) = numeric::split_literal(literal)?;
+                let scale = (10 as $t).checked_pow(u32::try_from(exponent).ok()?)?;
+                let value = digits.parse::<$t>().ok()?.checked_mul(scale)?;
+                if negative { value.checked_neg() } else { Some(value) }
+            }
+
+            fn to_f64(&self) -> f64 {
+                *self as f64
+            }
+
+            fn is_zero(&self) -> bool {
+                *self == 0
+            }
+
+            fn add(&self, rhs: &Self) -> Result<Self, NumError> {
+                self.checked_add(*rhs).ok_or(NumError::Overflow)
+            }
+
+            fn sub(&self, rhs: &Self) -> Result<Self, NumError> {
+                self.checked_sub(*rhs).ok_or(NumError::Overflow)
+            }
+
+            fn mul(&self, rhs: &Self) -> Result<Self, NumError> {
+                self.checked_mul(*rhs).ok_or(NumError::Overflow)
+            }
+
+            fn neg(&self) -> Result<Self, NumError> {
+                self.checked_neg().ok_or(NumError::Overflow)
+            }
+
+            fn div(&self, rhs: &Self) -> Result<Self, NumError> {
+                let quotient = self.checked_div(*rhs).ok_or(NumError::Overflow)?;
+                if self.wrapping_rem(*rhs) != 0 {
+                    return Err(NumError::Inexact);
+                }
+                Ok(quotient)
+            }
+
+            fn rem(&self, rhs: &Self) -> Result<Self, NumError> {
+                Ok(self.wrapping_rem(*rhs))
+            }
+
+            // A negative exponent only has an integer result for a base of 1 or -1
+            fn pow(&self, exponent: &Self) -> Result<Self, NumError> {
+                let odd = exponent % 2 != 0;
+                match *self {
+                    0 if *exponent > 0 => Ok(0),
+                    1 => Ok(1),
+                    -1 => Ok(if odd { -1 } else { 1 }),
+                    _ if *exponent < 0 => Err(NumError::Inexact),
+                    _ => u32::try_from(*exponent)
+                        .ok()
+                        .and_then(|exp| self.checked_pow(exp))
+                        .ok_or(NumError::Overflow),
+                }
+            }
+
+            fn bit_and(&self, rhs: &Self) -> Result<Self, NumError> {
+                Ok(self & rhs)
+            }
+
+            fn bit_or(&self, rhs: &Self) -> Result<Self, NumError> {
+                Ok(self | rhs)
+            }
+
+            fn bit_xor(&self, rhs: &Self) -> Result<Self, NumError> {
+                Ok(self ^ rhs)
+            }
+
+            fn bit_not(&self) -> Result<Self, NumError> {
+                Ok(!self)
+            }
+
+            // Shifting out set bits, or into the sign bit, overflows
+            fn shl(&self, amount: u32) -> Result<Self, NumError> {
+                if *self == 0 {
+                    return Ok(0);
+                }
+                let shifted = self.checked_shl(amount).ok_or(NumError::Overflow)?;
+                if shifted >> amount != *self {
+                    return Err(NumError::Overflow);
+                }
+                Ok(shifted)
+            }
+
+            // Arithmetic shift, so shifting everything out leaves 0 or -1
+            fn shr(&self, amount: u32) -> Result<Self, NumError> {
+                Ok(self >> amount.min(<$t>::BITS - 1))
+            }
+        }
+    )*};
+}
+
+impl_numeric_integer!(i64, i128);
+
+// Unit tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_checked_arithmetic() {
+        assert_eq!(i64::MAX.add(&1), Err(NumError::Overflow));
+        assert_eq!(i64::MIN.neg(), Err(NumError::Overflow));
+        assert_eq!(i64::MIN.div(&-1), Err(NumError::Overflow));
+        assert_eq!(7i64.div(&2), Err(NumError::Inexact));
+        assert_eq!((-8i128).div(&2), Ok(-4));
+        assert_eq!(Numeric::pow(&2i64, &62), Ok(1 << 62));
+        assert_eq!(Numeric::pow(&2i64, &63), Err(NumError::Overflow));
+        assert_eq!(Numeric::pow(&2i128, &-1), Err(NumError::Inexact));
+        assert_eq!(Numeric::pow(&-1i64, &-3), Ok(-1));
+    }
+
+    #[test]
+    fn test_floored_division() {
+        assert_eq!((-7i64).floor_div(&2), Ok(-4));
+        assert_eq!(7i64.floor_div(&-2), Ok(-4));
+        assert_eq!((-7i64).modulo(&2), Ok(1));
+        assert_eq!(7i64.modulo(&-2), Ok(-1));
+        assert_eq!((-7i64).rem(&2), Ok(-1));
+        assert_eq!(i64::MIN.modulo(&-1), Ok(0));
+        assert_eq!(i64::MIN.floor_div(&3), Ok(i64::MIN / 3 - 1));
+        assert_eq!(i64::MIN.floor_div(&-1), Err(NumError::Overflow));
+    }
+
+    #[test]
+    fn test_shifts() {
+        assert_eq!(1i64.shl(62), Ok(1 << 62));
+        assert_eq!(1i64.shl(63), Err(NumError::Overflow));
+        assert_eq!(3i128.shl(127), Err(NumError::Overflow));
+        assert_eq!((-1i64).shl(63), Ok(i64::MIN));
+        assert_eq!((-16i64).shr(200), Ok(-1));
+    }
+
+    #[test]
+    fn test_conversions() {
+        assert_eq!(i64::from_f64(42.0), Some(42));
+        assert_eq!(i64::from_f64(0.5), None);
+        assert_eq!(i64::from_f64(1e300), None);
+        assert_eq!(i64::from_u128(u64::MAX as u128), None);
+        assert_eq!(i128::from_u128(u64::MAX as u128), Some(u64::MAX as i128));
+        assert_eq!(i64::from_literal("9007199254740993"), Some(9_007_199_254_740_993));
+        assert_eq!(i64::from_literal("2.50e3"), Some(2500));
+        assert_eq!(i64::from_literal("2.5"), None);
+    }
+}
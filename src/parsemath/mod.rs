@@ -1,5 +1,18 @@
 /// Module Parsemath
 pub mod ast;
+#[cfg(feature = "bignum")]
+pub mod bignum;
+pub mod constants;
+pub mod context;
+pub mod decimal;
+pub mod float;
+pub mod functions;
+pub mod integer;
+pub mod numeric;
+pub mod operators;
 pub mod parser;
+pub mod rational;
+pub mod span;
 pub mod token;
 pub mod tokenizer;
+pub mod value;
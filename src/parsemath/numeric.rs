@@ -0,0 +1,490 @@
+/// This module contains the Numeric trait for the number types an expression can be evaluated
+/// with, such as f64, exact integers or a type provided by the application, and the evaluator
+/// shared by all of them.
+// Standard lib
+use std::fmt;
+
+// Internal modules
+use super::ast::{EvalError, Expr, Node};
+use super::context::Context;
+use super::functions::{self, Arity, NativeFn};
+use super::span::Span;
+use super::value::Value;
+
+// Largest integer up to which every integral f64 is exact (2^53)
+pub(crate) const MAX_EXACT_F64: f64 = 9_007_199_254_740_992.0;
+
+// Reason a checked numeric operation has no result
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NumError {
+    // The result does not fit into the number type
+    Overflow,
+    // The result is not representable exactly, e.g. 7 / 2 for integers
+    Inexact,
+    // The operation has no result for these operands, e.g. (-8)^(1/3) or (-1)!
+    Undefined,
+}
+
+// Arithmetic a number type provides to the evaluator. Exact types fail instead of rounding or
+// wrapping around, while f64 only fails where its result is meaningless, e.g. 10^400. Division-like
+// operations are never called with a zero divisor, and pow is never called with a zero base
+// and a negative exponent.
+pub trait Numeric: Clone + PartialOrd + fmt::Debug + fmt::Display {
+    // Name of the type in error messages, e.g. "i64"
+    const NAME: &'static str;
+
+    // Exact conversions of literals, variables and function results
+    fn from_f64(value: f64) -> Option<Self>;
+    fn from_u128(value: u128) -> Option<Self>;
+    // Decimal literal as typed, e.g. "0.1" or "2.5e-3", see ParseOptions::exact_decimals
+    fn from_literal(digits: &str) -> Option<Self> {
+        Self::from_f64(digits.parse().ok()?)
+    }
+    // Whether a result of a function or custom operator, computed in f64, is taken as exact.
+    // Types that hold fractions can reject results that are rounded, e.g. sqrt(2).
+    fn is_exact_result(value: f64) -> bool {
+        let _ = value;
+        true
+    }
+    // Functions and custom operators work on f64, so their arguments are converted
+    fn to_f64(&self) -> f64;
+    fn is_zero(&self) -> bool;
+    // Whether the value is accepted as an operand of the bitwise operators
+    fn is_integer(&self) -> bool {
+        Self::from_u128(1)
+            .and_then(|one| self.rem(&one).ok())
+            .is_some_and(|rem| rem.is_zero())
+    }
+
+    fn add(&self, rhs: &Self) -> Result<Self, NumError>;
+    fn sub(&self, rhs: &Self) -> Result<Self, NumError>;
+    fn mul(&self, rhs: &Self) -> Result<Self, NumError>;
+    fn neg(&self) -> Result<Self, NumError>;
+    fn div(&self, rhs: &Self) -> Result<Self, NumError>;
+    // Truncated remainder, the sign follows the dividend, e.g. -7 rem 2 == -1
+    fn rem(&self, rhs: &Self) -> Result<Self, NumError>;
+    // Floored division and modulo, derived from the remainder: the truncated quotient is
+    // (self - rem) / rhs, which divides exactly, and is one too large when rem and rhs have
+    // different signs. The modulo takes the sign of the divisor, e.g. -7 mod 2 == 1.
+    fn floor_div(&self, rhs: &Self) -> Result<Self, NumError> {
+        let rem = self.rem(rhs)?;
+        let quotient = self.sub(&rem)?.div(rhs)?;
+        if rounds_towards_zero(&rem, rhs)? {
+            return quotient.sub(&Self::from_u128(1).ok_or(NumError::Overflow)?);
+        }
+        Ok(quotient)
+    }
+    fn modulo(&self, rhs: &Self) -> Result<Self, NumError> {
+        let rem = self.rem(rhs)?;
+        if rounds_towards_zero(&rem, rhs)? {
+            return rem.add(rhs);
+        }
+        Ok(rem)
+    }
+    fn pow(&self, exponent: &Self) -> Result<Self, NumError>;
+    // Product 1 * 2 * ... * n, only defined for non-negative integers
+    fn factorial(&self) -> Result<Self, NumError> {
+        let (zero, one) = match (Self::from_u128(0), Self::from_u128(1)) {
+            (Some(zero), Some(one)) => (zero, one),
+            _ => return Err(NumError::Overflow),
+        };
+        if *self < zero || !self.is_integer() {
+            return Err(NumError::Undefined);
+        }
+        let (mut k, mut res) = (one.clone(), one.clone());
+        while k < *self {
+            k = k.add(&one)?;
+            res = res.mul(&k)?;
+        }
+        Ok(res)
+    }
+
+    // Bitwise operators work on the two's complement representation.
+    // They are only called with integral operands.
+    fn bit_and(&self, rhs: &Self) -> Result<Self, NumError>;
+    fn bit_or(&self, rhs: &Self) -> Result<Self, NumError>;
+    fn bit_xor(&self, rhs: &Self) -> Result<Self, NumError>;
+    fn bit_not(&self) -> Result<Self, NumError>;
+    fn shl(&self, amount: u32) -> Result<Self, NumError>;
+    fn shr(&self, amount: u32) -> Result<Self, NumError>;
+
+    // Error reported when the operator op overflows for these operands
+    fn overflow_error(op: &'static str, _operands: &[Self], span: Span) -> EvalError {
+        EvalError::NumericOverflow { op, ty: Self::NAME, span }
+    }
+}
+
+// Result of an exact evaluation that falls back to f64 when an operation has no exact result
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Evaluated<N> {
+    Exact(Value<N>),
+    Promoted(Value<f64>),
+}
+
+impl<N: fmt::Display> fmt::Display for Evaluated<N> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Evaluated::Exact(value) => write!(f, "{}", value),
+            Evaluated::Promoted(value) => write!(f, "{}", value),
+        }
+    }
+}
+
+// Evaluate an AST with the number type N, resolving variables against the context.
+// ast::eval_with is this evaluator with N = f64.
+pub fn eval_with<N: Numeric>(expr: &Expr, ctx: &Context) -> Result<Value<N>, EvalError> {
+    use Node::*;
+    let eval = |expr: &Expr| eval_number::<N>(expr, ctx);
+    let eval_bool = |expr: &Expr| eval_bool::<N>(expr, ctx);
+    let span = expr.span;
+    let constant = |value| constant::<N>(value, span);
+    // Apply a checked operation, attaching the operator, operands and location to its error
+    let apply = |op: &'static str, operands: &[N], result: Result<N, NumError>| {
+        result.map_err(|err| num_error::<N>(op, err, operands, span))
+    };
+    let unary = |op, n: N, f: fn(&N) -> Result<N, NumError>| {
+        let result = f(&n);
+        apply(op, &[n], result)
+    };
+    let binary = |op, a: N, b: N, f: fn(&N, &N) -> Result<N, NumError>| {
+        let result = f(&a, &b);
+        apply(op, &[a, b], result)
+    };
+    // Bitwise operators only accept integral operands
+    let int = |op: &'static str, expr: &Expr| {
+        let value = eval(expr)?;
+        if !value.is_integer() {
+            return Err(EvalError::NonIntegerBitwiseOperand { op, value: value.to_f64(), span });
+        }
+        Ok(value)
+    };
+    let divisor = |expr: &Expr, dividend: &N| {
+        let denom = eval(expr)?;
+        if denom.is_zero() {
+            return Err(EvalError::DivisionByZero { dividend: dividend.to_f64(), span });
+        }
+        Ok(denom)
+    };
+    match &expr.node {
+        Number(n) => from_f64(*n, span).map(Value::Number),
+        Decimal(digits) => match N::from_literal(digits) {
+            Some(n) => Ok(Value::Number(n)),
+            None => match digits.parse() {
+                Ok(value) => Err(EvalError::NotRepresentable { value, ty: N::NAME, span }),
+                Err(_) => Err(EvalError::InvalidLiteral { literal: digits.clone(), span }),
+            },
+        },
+        Integer(i) => N::from_u128(*i)
+            .map(Value::Number)
+            .ok_or(EvalError::NotRepresentable { value: *i as f64, ty: N::NAME, span }),
+        Bool(b) => Ok(Value::Bool(*b)),
+        Variable(name) => match ctx.resolve(name) {
+            Some(Value::Number(n)) => from_f64(n, span).map(Value::Number),
+            Some(Value::Bool(b)) => Ok(Value::Bool(b)),
+            None => Err(EvalError::UnknownVariable { name: name.clone(), span }),
+        },
+        Call(name, args) => {
+            // Host functions registered in the context shadow the built-ins
+            let (arity, func): (Arity, &NativeFn) = match ctx.get_fn(name) {
+                Some(host) => (host.arity, host.func.as_ref()),
+                None => match functions::lookup(name) {
+                    Some(builtin) => (builtin.arity, &builtin.func),
+                    None => return Err(EvalError::UnknownFunction { name: name.clone(), span }),
+                },
+            };
+            if !arity.accepts(args.len()) {
+                return Err(EvalError::ArityMismatch {
+                    name: name.clone(),
+                    expected: arity,
+                    found: args.len(),
+                    span,
+                });
+            }
+            let args: Vec<f64> =
+                args.iter().map(|arg| Ok(eval(arg)?.to_f64())).collect::<Result<_, _>>()?;
+            match func(&args) {
+                Some(res) => from_result(res, "a function call", span).map(Value::Number),
+                None => Err(EvalError::DomainError { name: name.clone(), args, span }),
+            }
+        }
+        Operator(fixity, symbol, operands) => {
+            let Some(operator) = ctx.operators().get(symbol, *fixity) else {
+                return Err(EvalError::UnknownOperator { symbol: symbol.clone(), span });
+            };
+            let args: Vec<f64> =
+                operands.iter().map(|arg| Ok(eval(arg)?.to_f64())).collect::<Result<_, _>>()?;
+            match operator.call(&args) {
+                Some(res) => from_result(res, "a custom operator", span).map(Value::Number),
+                None => Err(EvalError::DomainError { name: symbol.clone(), args, span }),
+            }
+        }
+        Assign(name, _) => Err(EvalError::AssignWithoutSession { name: name.clone(), span }),
+        Positive(expr) => Ok(Value::Number(eval(expr)?)),
+        Negative(expr) => unary("-", eval(expr)?, N::neg).map(Value::Number),
+        Factorial(expr) => unary("!", eval(expr)?, N::factorial).map(Value::Number),
+        Percent(expr) => binary("%", eval(expr)?, constant(100)?, N::div).map(Value::Number),
+        AddPercent(expr1, expr2) | SubtractPercent(expr1, expr2) => {
+            let base = eval(expr1)?;
+            // base * x / 100 rather than base * (x / 100), so that e.g. 150 + 10% stays exact
+            let share = match &expr2.node {
+                Percent(pct) => {
+                    let product = binary("*", base.clone(), eval(pct)?, N::mul)?;
+                    binary("%", product, constant(100)?, N::div)?
+                }
+                _ => binary("*", base.clone(), eval(expr2)?, N::mul)?,
+            };
+            match expr.node {
+                AddPercent(..) => binary("+", base, share, N::add),
+                _ => binary("-", base, share, N::sub),
+            }
+            .map(Value::Number)
+        }
+        Add(expr1, expr2) => binary("+", eval(expr1)?, eval(expr2)?, N::add).map(Value::Number),
+        Subtract(expr1, expr2) => {
+            binary("-", eval(expr1)?, eval(expr2)?, N::sub).map(Value::Number)
+        }
+        Multiply(expr1, expr2) => {
+            binary("*", eval(expr1)?, eval(expr2)?, N::mul).map(Value::Number)
+        }
+        Divide(expr1, expr2) => {
+            let dividend = eval(expr1)?;
+            let denom = divisor(expr2, &dividend)?;
+            binary("/", dividend, denom, N::div).map(Value::Number)
+        }
+        FloorDivide(expr1, expr2) => {
+            let dividend = eval(expr1)?;
+            let denom = divisor(expr2, &dividend)?;
+            binary("//", dividend, denom, N::floor_div).map(Value::Number)
+        }
+        Modulo(expr1, expr2) => {
+            let dividend = eval(expr1)?;
+            let denom = divisor(expr2, &dividend)?;
+            binary("%", dividend, denom, N::modulo).map(Value::Number)
+        }
+        Remainder(expr1, expr2) => {
+            let dividend = eval(expr1)?;
+            let denom = divisor(expr2, &dividend)?;
+            binary("rem", dividend, denom, N::rem).map(Value::Number)
+        }
+        Caret(expr1, expr2) => {
+            let base = eval(expr1)?;
+            let exponent = eval(expr2)?;
+            let zero = constant(0)?;
+            if base == zero && exponent < zero {
+                return Err(EvalError::ZeroToNegativePower { exponent: exponent.to_f64(), span });
+            }
+            match base.pow(&exponent) {
+                Err(NumError::Undefined) => Err(EvalError::NegativeBaseFractionalExponent {
+                    base: base.to_f64(),
+                    exponent: exponent.to_f64(),
+                    span,
+                }),
+                result => apply("^", &[base, exponent], result).map(Value::Number),
+            }
+        }
+        And(expr1, expr2) => binary("&", int("&", expr1)?, int("&", expr2)?, N::bit_and)
+            .map(Value::Number),
+        Or(expr1, expr2) => binary("|", int("|", expr1)?, int("|", expr2)?, N::bit_or)
+            .map(Value::Number),
+        Xor(expr1, expr2) => binary("xor", int("xor", expr1)?, int("xor", expr2)?, N::bit_xor)
+            .map(Value::Number),
+        BitNot(expr) => unary("~", int("~", expr)?, N::bit_not).map(Value::Number),
+        ShiftLeft(expr1, expr2) | ShiftRight(expr1, expr2) => {
+            let op = if matches!(expr.node, ShiftLeft(..)) { "<<" } else { ">>" };
+            let value = int(op, expr1)?;
+            let amount = eval(expr2)?;
+            let bits = shift_amount(op, &amount, span)?;
+            let result = if op == "<<" { value.shl(bits) } else { value.shr(bits) };
+            apply(op, &[value, amount], result).map(Value::Number)
+        }
+        // == and != compare two numbers or two booleans
+        Equal(expr1, expr2) | NotEqual(expr1, expr2) => {
+            let left = eval_with::<N>(expr1, ctx)?;
+            let right = eval_with::<N>(expr2, ctx)?;
+            if left.type_name() != right.type_name() {
+                return Err(EvalError::TypeMismatch {
+                    expected: left.type_name(),
+                    found: right.type_name(),
+                    span: expr2.span,
+                });
+            }
+            let equal = match (left, right) {
+                (Value::Number(a), Value::Number(b)) => a == b,
+                (a, b) => a.as_bool() == b.as_bool(),
+            };
+            Ok(Value::Bool(equal == matches!(expr.node, Equal(..))))
+        }
+        Less(expr1, expr2) => Ok(Value::Bool(eval(expr1)? < eval(expr2)?)),
+        LessEqual(expr1, expr2) => Ok(Value::Bool(eval(expr1)? <= eval(expr2)?)),
+        Greater(expr1, expr2) => Ok(Value::Bool(eval(expr1)? > eval(expr2)?)),
+        GreaterEqual(expr1, expr2) => Ok(Value::Bool(eval(expr1)? >= eval(expr2)?)),
+        // && and || short-circuit, so the right side is only evaluated when needed
+        LogicalAnd(expr1, expr2) => Ok(Value::Bool(eval_bool(expr1)? && eval_bool(expr2)?)),
+        LogicalOr(expr1, expr2) => Ok(Value::Bool(eval_bool(expr1)? || eval_bool(expr2)?)),
+        Not(expr) => Ok(Value::Bool(!eval_bool(expr)?)),
+        Conditional(cond, then_expr, else_expr) => {
+            if eval_bool(cond)? {
+                eval_with(then_expr, ctx)
+            } else {
+                eval_with(else_expr, ctx)
+            }
+        }
+    }
+}
+
+// Evaluate an operand that must be a number
+fn eval_number<N: Numeric>(expr: &Expr, ctx: &Context) -> Result<N, EvalError> {
+    match eval_with::<N>(expr, ctx)? {
+        Value::Number(n) => Ok(n),
+        other => Err(EvalError::TypeMismatch {
+            expected: "number",
+            found: other.type_name(),
+            span: expr.span,
+        }),
+    }
+}
+
+// Evaluate an operand that must be a boolean
+fn eval_bool<N: Numeric>(expr: &Expr, ctx: &Context) -> Result<bool, EvalError> {
+    match eval_with::<N>(expr, ctx)? {
+        Value::Bool(b) => Ok(b),
+        other => Err(EvalError::TypeMismatch {
+            expected: "bool",
+            found: other.type_name(),
+            span: expr.span,
+        }),
+    }
+}
+
+fn from_f64<N: Numeric>(value: f64, span: Span) -> Result<N, EvalError> {
+    N::from_f64(value).ok_or(EvalError::NotRepresentable { value, ty: N::NAME, span })
+}
+
+fn from_result<N: Numeric>(value: f64, op: &'static str, span: Span) -> Result<N, EvalError> {
+    if value.is_finite() && !N::is_exact_result(value) {
+        return Err(EvalError::InexactResult { op, ty: N::NAME, span });
+    }
+    from_f64(value, span)
+}
+
+// Whether a truncated division left a remainder of the opposite sign to the divisor,
+// i.e. rounded a negative quotient up
+fn rounds_towards_zero<N: Numeric>(rem: &N, rhs: &N) -> Result<bool, NumError> {
+    let zero = N::from_u128(0).ok_or(NumError::Overflow)?;
+    Ok(*rem != zero && (*rem < zero) != (*rhs < zero))
+}
+
+// Small integer constant such as 100 for percentages, which a number type may still not hold,
+// e.g. Decimal<37>
+fn constant<N: Numeric>(value: u8, span: Span) -> Result<N, EvalError> {
+    N::from_u128(value as u128).ok_or(EvalError::NotRepresentable {
+        value: value as f64,
+        ty: N::NAME,
+        span,
+    })
+}
+
+// Split a decimal literal into its sign, digits and power of ten, e.g. "12.50e-3" into
+// (false, "125", -4). The digits have no leading or trailing zeros, and are "0" for zero.
+pub(crate) fn split_literal(literal: &str) -> Option<(bool, String, i32)> {
+    let (negative, literal) = match literal.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, literal),
+    };
+    let (mantissa, exponent) = match literal.split_once(['e', 'E']) {
+        Some((mantissa, exponent)) => (mantissa, exponent.parse::<i32>().ok()?),
+        None => (literal, 0),
+    };
+    let (int, frac) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+    if !int.chars().chain(frac.chars()).all(|c| c.is_ascii_digit()) || int.len() + frac.len() == 0 {
+        return None;
+    }
+    let digits = format!("{}{}", int, frac);
+    let digits = digits.trim_start_matches('0');
+    let trimmed = digits.trim_end_matches('0');
+    if trimmed.is_empty() {
+        return Some((negative, "0".into(), 0));
+    }
+    let zeros = i32::try_from(digits.len() - trimmed.len()).ok()?;
+    let frac_len = i32::try_from(frac.len()).ok()?;
+    Some((negative, trimmed.into(), exponent.checked_add(zeros)?.checked_sub(frac_len)?))
+}
+
+// Shift amounts must be non-negative integers
+fn shift_amount<N: Numeric>(op: &'static str, amount: &N, span: Span) -> Result<u32, EvalError> {
+    let value = amount.to_f64();
+    if !amount.is_integer() {
+        return Err(EvalError::NonIntegerBitwiseOperand { op, value, span });
+    }
+    let amount = value as i64;
+    u32::try_from(amount).map_err(|_| EvalError::ShiftOutOfRange { op, amount, span })
+}
+
+fn num_error<N: Numeric>(op: &'static str, err: NumError, operands: &[N], span: Span) -> EvalError {
+    match err {
+        NumError::Overflow => N::overflow_error(op, operands, span),
+        NumError::Inexact => EvalError::InexactResult { op, ty: N::NAME, span },
+        NumError::Undefined => {
+            let args = operands.iter().map(N::to_f64).collect();
+            EvalError::DomainError { name: op.into(), args, span }
+        }
+    }
+}
+
+// Unit tests
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::parsemath::parser::Parser;
+
+    // Evaluate an expression with the number type N, for the tests of the number types
+    pub(crate) fn eval<N: Numeric>(expr: &str) -> Result<Value<N>, EvalError> {
+        let ast = Parser::new(expr).unwrap().parse().unwrap();
+        eval_with(&ast, &Context::new())
+    }
+
+    #[test]
+    fn test_exact_integers() {
+        assert_eq!(eval::<i64>("2^60 + 1"), Ok(Value::Number((1 << 60) + 1)));
+        assert_eq!(eval::<i64>("(2^53 + 1) - 2^53"), Ok(Value::Number(1)));
+        assert_eq!(eval::<i64>("-7 // 2 + -7 mod 2"), Ok(Value::Number(-3)));
+        assert_eq!(eval::<i64>("0xFF & 0b1010 | 1 << 4"), Ok(Value::Number(26)));
+        assert_eq!(eval::<i128>("20! / 19!"), Ok(Value::Number(20)));
+        assert_eq!(eval::<i64>("6 / 3 == 2 && 1 < 2"), Ok(Value::Bool(true)));
+    }
+
+    #[test]
+    fn test_overflow() {
+        assert!(matches!(
+            eval::<i64>("2^63"),
+            Err(EvalError::NumericOverflow { op: "^", ty: "i64", .. })
+        ));
+        assert!(matches!(eval::<i64>("21!"), Err(EvalError::NumericOverflow { op: "!", .. })));
+        assert_eq!(eval::<i128>("2^63"), Ok(Value::Number(1 << 63)));
+        assert!(matches!(
+            eval::<i128>("2^126 * 2"),
+            Err(EvalError::NumericOverflow { op: "*", ty: "i128", .. })
+        ));
+    }
+
+    #[test]
+    fn test_split_literal() {
+        assert_eq!(split_literal("12.50e-3"), Some((false, "125".into(), -4)));
+        assert_eq!(split_literal("-1200"), Some((true, "12".into(), 2)));
+        assert_eq!(split_literal(".5"), Some((false, "5".into(), -1)));
+        assert_eq!(split_literal("000.000"), Some((false, "0".into(), 0)));
+        assert_eq!(split_literal("1.2.3"), None);
+        assert_eq!(split_literal("e5"), None);
+    }
+
+    #[test]
+    fn test_inexact_and_unrepresentable() {
+        assert!(matches!(eval::<i64>("7 / 2"), Err(EvalError::InexactResult { op: "/", .. })));
+        assert!(matches!(eval::<i64>("2^-1"), Err(EvalError::InexactResult { op: "^", .. })));
+        assert!(matches!(eval::<i64>("5%"), Err(EvalError::InexactResult { op: "%", .. })));
+        assert!(matches!(eval::<i64>("1 / 0"), Err(EvalError::DivisionByZero { .. })));
+        assert!(matches!(eval::<i64>("1.5 * 2"), Err(EvalError::NotRepresentable { .. })));
+        assert!(matches!(eval::<i64>("sqrt(2)"), Err(EvalError::NotRepresentable { .. })));
+        assert_eq!(eval::<i64>("sqrt(16) + 1"), Ok(Value::Number(5)));
+    }
+}
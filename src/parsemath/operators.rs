@@ -0,0 +1,161 @@
+/// This module contains custom operators registered by the host application at runtime,
+/// e.g. an infix `±` or a prefix `√`, together with the table the tokenizer and parser consult.
+// Standard lib
+use std::fmt;
+use std::sync::Arc;
+
+// Internal modules
+use super::functions::NativeFn;
+use super::token::{Assoc, OperPrec};
+
+// Position of an operator relative to its operands
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Fixity {
+    Prefix,
+    Infix,
+    Postfix,
+}
+
+// Custom operator. The closure receives one operand, or two for an infix operator,
+// and returns None when they are outside of its domain.
+#[derive(Clone)]
+pub struct Operator {
+    pub symbol: String,
+    pub fixity: Fixity,
+    pub prec: OperPrec,
+    pub assoc: Assoc,
+    pub(crate) func: Arc<NativeFn>,
+}
+
+impl Operator {
+    // Binary operator, e.g. Operator::infix("±", OperPrec::AddSub, Assoc::Left, ...)
+    pub fn infix<F>(symbol: impl Into<String>, prec: OperPrec, assoc: Assoc, func: F) -> Self
+    where
+        F: Fn(f64, f64) -> Option<f64> + Send + Sync + 'static,
+    {
+        Operator {
+            symbol: symbol.into(),
+            fixity: Fixity::Infix,
+            prec,
+            assoc,
+            func: Arc::new(move |a: &[f64]| func(a[0], a[1])),
+        }
+    }
+
+    // Unary operator before its operand, which is parsed at the given precedence
+    pub fn prefix<F>(symbol: impl Into<String>, prec: OperPrec, func: F) -> Self
+    where
+        F: Fn(f64) -> Option<f64> + Send + Sync + 'static,
+    {
+        Operator::unary(symbol, Fixity::Prefix, prec, func)
+    }
+
+    // Unary operator after its operand, applied to any operator binding tighter than prec
+    pub fn postfix<F>(symbol: impl Into<String>, prec: OperPrec, func: F) -> Self
+    where
+        F: Fn(f64) -> Option<f64> + Send + Sync + 'static,
+    {
+        Operator::unary(symbol, Fixity::Postfix, prec, func)
+    }
+
+    fn unary<F>(symbol: impl Into<String>, fixity: Fixity, prec: OperPrec, func: F) -> Self
+    where
+        F: Fn(f64) -> Option<f64> + Send + Sync + 'static,
+    {
+        Operator {
+            symbol: symbol.into(),
+            fixity,
+            prec,
+            assoc: Assoc::Left,
+            func: Arc::new(move |a: &[f64]| func(a[0])),
+        }
+    }
+
+    pub fn call(&self, args: &[f64]) -> Option<f64> {
+        (self.func)(args)
+    }
+}
+
+impl fmt::Debug for Operator {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Operator")
+            .field("symbol", &self.symbol)
+            .field("fixity", &self.fixity)
+            .field("prec", &self.prec)
+            .field("assoc", &self.assoc)
+            .finish_non_exhaustive()
+    }
+}
+
+// Custom operators known to an evaluation context.
+// A symbol may be registered once per fixity, e.g. both as a prefix and as an infix operator.
+#[derive(Debug, Clone, Default)]
+pub struct OperatorTable {
+    operators: Vec<Operator>,
+}
+
+// Used when no custom operators are registered
+pub(crate) static NO_OPERATORS: OperatorTable = OperatorTable::new();
+
+impl OperatorTable {
+    pub const fn new() -> Self {
+        OperatorTable { operators: Vec::new() }
+    }
+
+    // Add an operator, replacing one with the same symbol and fixity
+    pub fn insert(&mut self, operator: Operator) {
+        self.operators.retain(|op| op.symbol != operator.symbol || op.fixity != operator.fixity);
+        self.operators.push(operator);
+    }
+
+    pub fn get(&self, symbol: &str, fixity: Fixity) -> Option<&Operator> {
+        self.operators.iter().find(|op| op.symbol == symbol && op.fixity == fixity)
+    }
+
+    // Longest registered symbol at the start of the input. A symbol ending in a letter or
+    // digit must not run into an identifier, so "dot" does not match the start of "dots".
+    pub(crate) fn match_symbol(&self, input: &str) -> Option<&str> {
+        self.operators
+            .iter()
+            .map(|op| op.symbol.as_str())
+            .filter(|symbol| !symbol.is_empty() && input.starts_with(symbol))
+            .filter(|symbol| {
+                let word_end = symbol.ends_with(|c: char| c.is_ascii_alphanumeric() || c == '_');
+                let next = input[symbol.len()..].chars().next();
+                !(word_end && next.is_some_and(|c| c.is_ascii_alphanumeric() || c == '_'))
+            })
+            .max_by_key(|symbol| symbol.len())
+    }
+}
+
+// Unit tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_insert_and_get() {
+        let mut table = OperatorTable::new();
+        table.insert(Operator::infix("±", OperPrec::AddSub, Assoc::Left, |a, b| Some(a + b)));
+        table.insert(Operator::prefix("±", OperPrec::Negative, |a| Some(a.abs())));
+        table.insert(Operator::infix("±", OperPrec::AddSub, Assoc::Left, |a, b| Some(a - b)));
+
+        assert_eq!(table.get("±", Fixity::Infix).unwrap().call(&[5.0, 3.0]), Some(2.0));
+        assert_eq!(table.get("±", Fixity::Prefix).unwrap().call(&[-5.0]), Some(5.0));
+        assert!(table.get("±", Fixity::Postfix).is_none());
+    }
+
+    #[test]
+    fn test_match_symbol() {
+        let mut table = OperatorTable::new();
+        table.insert(Operator::infix("*", OperPrec::MulDiv, Assoc::Left, |a, b| Some(a * b)));
+        table.insert(Operator::infix("**", OperPrec::Power, Assoc::Right, |a, b| Some(a.powf(b))));
+        table.insert(Operator::infix("dot", OperPrec::MulDiv, Assoc::Left, |a, b| Some(a * b)));
+
+        assert_eq!(table.match_symbol("** 2"), Some("**"));
+        assert_eq!(table.match_symbol("* 2"), Some("*"));
+        assert_eq!(table.match_symbol("dot y"), Some("dot"));
+        assert_eq!(table.match_symbol("dots"), None);
+        assert_eq!(table.match_symbol("+ 2"), None);
+    }
+}
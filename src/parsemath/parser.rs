@@ -3,16 +3,119 @@
 use std::fmt;
 
 // Internal modules
-use super::ast::Node;
-use super::token::{OperPrec, Token};
-use super::tokenizer::Tokenizer;
+use super::ast::{Expr, Node};
+use super::operators::{Fixity, Operator, OperatorTable, NO_OPERATORS};
+use super::span::Span;
+use super::token::{Assoc, OperPrec, SpannedToken, Token};
+use super::tokenizer::{LexError, Tokenizer};
 
 //Structs and constants
 
+type UnaryNode = fn(Box<Expr>) -> Node;
+type BinaryNode = fn(Box<Expr>, Box<Expr>) -> Node;
+
+// AST node built by an infix operator
+#[derive(Clone)]
+enum Infix {
+    Binary(BinaryNode),
+    // cond ? a : b, which reads its own ':' branch
+    Conditional,
+    // Operator registered at runtime, by symbol
+    Custom(String),
+}
+
+// Prefix operators: token, precedence of the operand and the node built
+const PREFIX_OPERATORS: &[(Token, OperPrec, UnaryNode)] = &[
+    (Token::Add, OperPrec::Negative, Node::Positive),
+    (Token::Subtract, OperPrec::Negative, Node::Negative),
+    (Token::Bang, OperPrec::Negative, Node::Not),
+    (Token::Tilde, OperPrec::Negative, Node::BitNot),
+];
+
+// Postfix operators: token, precedence and the node built.
+// % after an operand is the percent operator unless another operand follows, e.g. 20% * 3.
+// An operator that is also infix, such as -, continues the expression: 20% - 5 is 0.2 - 5.
+const POSTFIX_OPERATORS: &[(Token, OperPrec, UnaryNode)] = &[
+    (Token::Bang, OperPrec::Postfix, Node::Factorial),
+    (Token::Percent, OperPrec::Postfix, Node::Percent),
+];
+
+// Infix operators: token, precedence, associativity and the node built.
+// Adding an operator only takes a token and a row here.
+const INFIX_OPERATORS: &[(Token, OperPrec, Assoc, Infix)] = &[
+    (Token::Question, OperPrec::Conditional, Assoc::Right, Infix::Conditional),
+    (Token::LogicalOr, OperPrec::LogicalOr, Assoc::Left, Infix::Binary(Node::LogicalOr)),
+    (Token::LogicalAnd, OperPrec::LogicalAnd, Assoc::Left, Infix::Binary(Node::LogicalAnd)),
+    (Token::Equal, OperPrec::Comparison, Assoc::Left, Infix::Binary(Node::Equal)),
+    (Token::NotEqual, OperPrec::Comparison, Assoc::Left, Infix::Binary(Node::NotEqual)),
+    (Token::Less, OperPrec::Comparison, Assoc::Left, Infix::Binary(Node::Less)),
+    (Token::LessEqual, OperPrec::Comparison, Assoc::Left, Infix::Binary(Node::LessEqual)),
+    (Token::Greater, OperPrec::Comparison, Assoc::Left, Infix::Binary(Node::Greater)),
+    (Token::GreaterEqual, OperPrec::Comparison, Assoc::Left, Infix::Binary(Node::GreaterEqual)),
+    (Token::Or, OperPrec::BitOr, Assoc::Left, Infix::Binary(Node::Or)),
+    (Token::Xor, OperPrec::BitXor, Assoc::Left, Infix::Binary(Node::Xor)),
+    (Token::And, OperPrec::BitAnd, Assoc::Left, Infix::Binary(Node::And)),
+    (Token::ShiftLeft, OperPrec::Shift, Assoc::Left, Infix::Binary(Node::ShiftLeft)),
+    (Token::ShiftRight, OperPrec::Shift, Assoc::Left, Infix::Binary(Node::ShiftRight)),
+    (Token::Add, OperPrec::AddSub, Assoc::Left, Infix::Binary(Node::Add)),
+    (Token::Subtract, OperPrec::AddSub, Assoc::Left, Infix::Binary(Node::Subtract)),
+    (Token::Multiply, OperPrec::MulDiv, Assoc::Left, Infix::Binary(Node::Multiply)),
+    (Token::Divide, OperPrec::MulDiv, Assoc::Left, Infix::Binary(Node::Divide)),
+    (Token::FloorDivide, OperPrec::MulDiv, Assoc::Left, Infix::Binary(Node::FloorDivide)),
+    // % and mod are both the floored modulo
+    (Token::Percent, OperPrec::MulDiv, Assoc::Left, Infix::Binary(Node::Modulo)),
+    (Token::Mod, OperPrec::MulDiv, Assoc::Left, Infix::Binary(Node::Modulo)),
+    (Token::Rem, OperPrec::MulDiv, Assoc::Left, Infix::Binary(Node::Remainder)),
+    (Token::Caret, OperPrec::Power, Assoc::Right, Infix::Binary(Node::Caret)),
+];
+
+fn prefix_operator(token: &Token) -> Option<(OperPrec, UnaryNode)> {
+    PREFIX_OPERATORS.iter().find(|op| op.0 == *token).map(|op| (op.1, op.2))
+}
+
+fn postfix_operator(token: &Token) -> Option<(OperPrec, UnaryNode)> {
+    POSTFIX_OPERATORS.iter().find(|op| op.0 == *token).map(|op| (op.1, op.2))
+}
+
+fn infix_operator(token: &Token) -> Option<(OperPrec, Assoc, Infix)> {
+    INFIX_OPERATORS.iter().find(|op| op.0 == *token).map(|op| (op.1, op.2, op.3.clone()))
+}
+
+// Options controlling how expressions are read
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParseOptions {
+    // Skip whitespace inside numbers, so "1 000" reads as 1000 instead of being rejected
+    pub lenient_numbers: bool,
+    // Calculator percentages: 100 + 10% is 110 and 100 - 10% is 90, instead of 100.1 and 99.9
+    pub calculator_percent: bool,
+    // Read juxtaposed operands such as 2pi or (1+2)(3+4) as a multiplication, see Parser::juxtaposed
+    pub implicit_multiplication: bool,
+    // Keep decimal literals as typed (Node::Decimal) for exact number types, see Tokenizer
+    pub exact_decimals: bool,
+}
+
+impl Default for ParseOptions {
+    fn default() -> Self {
+        ParseOptions {
+            lenient_numbers: false,
+            calculator_percent: false,
+            implicit_multiplication: true,
+            exact_decimals: false,
+        }
+    }
+}
+
 // Parser struct
 pub struct Parser<'a> {
     tokenizer: Tokenizer<'a>,
+    operators: &'a OperatorTable,
+    options: ParseOptions,
     current_token: Token,
+    current_span: Span,
+    // Whether the token before current_token was a closing parenthesis
+    follows_paren: bool,
+    // Token after current_token, once it had to be looked at
+    peeked: Option<SpannedToken>,
 }
 
 // Public methods of Parser
@@ -20,24 +123,51 @@ pub struct Parser<'a> {
 impl<'a> Parser<'a> {
     // Create a new instance of Parser
     pub fn new(expr: &'a str) -> Result<Self, ParseError> {
-        let mut lexer = Tokenizer::new(expr);
-        let cur_token = match lexer.next() {
-            Some(token) => token,
-            None => return Err(ParseError::InvalidOperator("Invalid character".into())),
+        Parser::with_options(expr, ParseOptions::default())
+    }
+
+    // Create a new instance of Parser reading the expression according to the options
+    pub fn with_options(expr: &'a str, options: ParseOptions) -> Result<Self, ParseError> {
+        Parser::with_operators(expr, options, &NO_OPERATORS)
+    }
+
+    // Create a new instance of Parser that also recognizes the given custom operators
+    pub fn with_operators(
+        expr: &'a str,
+        options: ParseOptions,
+        operators: &'a OperatorTable,
+    ) -> Result<Self, ParseError> {
+        let mut lexer = match options.lenient_numbers {
+            true => Tokenizer::lenient(expr),
+            false => Tokenizer::new(expr),
+        };
+        if options.exact_decimals {
+            lexer = lexer.exact_decimals();
+        }
+        let mut parser = Parser {
+            tokenizer: lexer.with_operators(operators),
+            operators,
+            options,
+            current_token: Token::EOF,
+            current_span: Span::default(),
+            follows_paren: false,
+            peeked: None,
         };
-        Ok(Parser {
-            tokenizer: lexer,
-            current_token: cur_token,
-        })
+        parser.get_next_token()?;
+        Ok(parser)
     }
 
     // Take an arithmetic expression as input and return an AST
-    pub fn parse(&mut self) -> Result<Node, ParseError> {
-        let ast = self.generate_ast(OperPrec::DefaultZero);
-        match ast {
-            Ok(res) => Ok(res),
-            Err(error) => Err(error),
+    pub fn parse(&mut self) -> Result<Expr, ParseError> {
+        let ast = self.parse_statement()?;
+        // The whole expression must be consumed, e.g. "(1+2))" is rejected
+        if self.current_token != Token::EOF {
+            return Err(ParseError::UnableToParse(
+                format!("Unexpected {}", self.current_token),
+                self.current_span,
+            ));
         }
+        Ok(ast)
     }
 }
 
@@ -45,65 +175,271 @@ impl<'a> Parser<'a> {
 
 impl<'a> Parser<'a> {
     // Retrieve the next token from arithmetic expression and set it to current_token field in Parser struct
+    // The end of input is represented by Token::EOF
     fn get_next_token(&mut self) -> Result<(), ParseError> {
-        let next_token = match self.tokenizer.next() {
-            Some(token) => token,
-            None => return Err(ParseError::InvalidOperator("Invalid character".into())),
+        let next_token = match self.peeked.take() {
+            Some(next_token) => next_token,
+            None => self.read_token()?,
         };
-        self.current_token = next_token;
+        self.follows_paren = self.current_token == Token::RightParen;
+        self.current_token = next_token.token;
+        self.current_span = next_token.span;
         Ok(())
     }
 
+    // Look at the token after current_token without moving past it
+    fn peek_token(&mut self) -> Result<&Token, ParseError> {
+        if self.peeked.is_none() {
+            self.peeked = Some(self.read_token()?);
+        }
+        Ok(&self.peeked.as_ref().unwrap().token)
+    }
+
+    fn read_token(&mut self) -> Result<SpannedToken, ParseError> {
+        match self.tokenizer.next() {
+            Some(Ok(next_token)) => Ok(next_token),
+            Some(Err(error)) => Err(error.into()),
+            None => Ok(SpannedToken { token: Token::EOF, span: self.tokenizer.end_span() }),
+        }
+    }
+
+    // Whether the token begins an operand, e.g. a number, a name, '(' or a prefix operator
+    fn starts_operand(&self, token: &Token) -> bool {
+        match token {
+            Token::Num(_) | Token::Decimal(_) | Token::Int(_) => true,
+            Token::Ident(_) | Token::Bool(_) | Token::LeftParen => true,
+            Token::Op(symbol) => self.operators.get(symbol, Fixity::Prefix).is_some(),
+            token => prefix_operator(token).is_some(),
+        }
+    }
+
+    // Whether the token can be read as an infix operator, e.g. + or -
+    fn is_infix(&self, token: &Token) -> bool {
+        match token {
+            Token::Op(symbol) => self.operators.get(symbol, Fixity::Infix).is_some(),
+            token => infix_operator(token).is_some(),
+        }
+    }
+
+    // Implicit multiplication: an operand directly followed by another one is multiplied with
+    // it. The second operand must start with a name or '(', or with a number if the first
+    // operand ends with ')'. So 2pi, 3sqrt(2), 2(3), (2)3 and (1+2)(3+4) are products, while
+    // 2 3 is an error. A name directly followed by '(' is always a function call, and prefix
+    // operators such as - never start a product, so (1+2)-3 is a subtraction.
+    // The product binds tighter than anything but postfix operators on its left, and takes a
+    // power as its right operand: -2x^2 is -(2*(x^2)) and 1/2x is 1/(2*x). An exponent ends
+    // before a product though, so 2^3(4) is (2^3)*4 and x^2y is (x^2)*y.
+    fn juxtaposed(&self) -> bool {
+        if !self.options.implicit_multiplication {
+            return false;
+        }
+        match self.current_token {
+            Token::Ident(_) | Token::LeftParen => true,
+            Token::Num(_) | Token::Decimal(_) | Token::Int(_) => self.follows_paren,
+            _ => false,
+        }
+    }
+
+    // Built-in postfix operator at the current token, if it is used as one
+    fn postfix(&mut self) -> Result<Option<(OperPrec, UnaryNode)>, ParseError> {
+        let Some(postfix) = postfix_operator(&self.current_token) else {
+            return Ok(None);
+        };
+        if self.current_token == Token::Percent {
+            let next = self.peek_token()?.clone();
+            if self.starts_operand(&next) && !self.is_infix(&next) {
+                return Ok(None);
+            }
+        }
+        Ok(Some(postfix))
+    }
+
+    // A statement is an expression, optionally preceded by assignments, e.g. x = y = 3*4
+    fn parse_statement(&mut self) -> Result<Expr, ParseError> {
+        if let Token::Ident(name) = self.current_token.clone() {
+            if *self.peek_token()? == Token::Assign {
+                let start = self.current_span;
+                self.get_next_token()?;
+                self.get_next_token()?;
+                let value = self.parse_statement()?;
+                let span = start.to(value.span);
+                return Ok(Expr::new(Node::Assign(name, Box::new(value)), span));
+            }
+        }
+        self.generate_ast(OperPrec::DefaultZero)
+    }
+
     // Main workhorse method that is called recursively
-    fn generate_ast(&mut self, oper_prec: OperPrec) -> Result<Node, ParseError> {
+    fn generate_ast(&mut self, oper_prec: OperPrec) -> Result<Expr, ParseError> {
+        self.parse_expr(oper_prec.binding_power())
+    }
+
+    // Pratt loop: parse an operand, then keep folding in infix operators binding tighter than min_bp
+    fn parse_expr(&mut self, min_bp: u8) -> Result<Expr, ParseError> {
         let mut left_expr = self.parse_number()?;
 
-        while oper_prec < self.current_token.get_oper_prec() {
-            if self.current_token == Token::EOF {
+        loop {
+            if let Some(op) = self.custom_operator(Fixity::Postfix) {
+                if op.prec.binding_power() <= min_bp {
+                    break;
+                }
+                left_expr = custom_node(op, vec![left_expr], self.current_span);
+                self.get_next_token()?;
+                continue;
+            }
+            if let Some((prec, node)) = self.postfix()? {
+                if prec.binding_power() <= min_bp {
+                    break;
+                }
+                let span = left_expr.span.to(self.current_span);
+                left_expr = Expr::new(node(Box::new(left_expr)), span);
+                self.get_next_token()?;
+                continue;
+            }
+            if self.juxtaposed() {
+                // min_bp while parsing the right operand of ^, which is right-associative
+                let exponent_bp = OperPrec::Power.binding_power() - 1;
+                if OperPrec::Implicit.binding_power() <= min_bp || min_bp == exponent_bp {
+                    break;
+                }
+                let right_expr = self.parse_expr(OperPrec::MulDiv.binding_power())?;
+                left_expr = binary(Node::Multiply, left_expr, right_expr);
+                continue;
+            }
+            let infix = match &self.current_token {
+                Token::Op(symbol) => self
+                    .custom_operator(Fixity::Infix)
+                    .map(|op| (op.prec, op.assoc, Infix::Custom(symbol.clone()))),
+                token => infix_operator(token),
+            };
+            let Some((prec, assoc, infix)) = infix else {
+                break;
+            };
+            let left_bp = prec.binding_power();
+            if left_bp <= min_bp {
                 break;
             }
-            let right_expr = self.convert_token_to_node(left_expr.clone())?;
-            left_expr = right_expr;
+            // A left-associative operator leaves the next operator of its level to this loop,
+            // a right-associative one lets it bind into its right operand
+            let right_bp = match assoc {
+                Assoc::Left => left_bp,
+                Assoc::Right => left_bp - 1,
+            };
+            let token = self.current_token.clone();
+            self.get_next_token()?; // Get right-side expression
+            left_expr = match infix {
+                Infix::Binary(node) => {
+                    let right_expr = self.parse_expr(right_bp)?;
+                    // In calculator mode a percentage is taken of the left operand
+                    let node = match (&token, &right_expr.node) {
+                        (Token::Add, Node::Percent(_)) if self.options.calculator_percent => {
+                            Node::AddPercent
+                        }
+                        (Token::Subtract, Node::Percent(_)) if self.options.calculator_percent => {
+                            Node::SubtractPercent
+                        }
+                        _ => node,
+                    };
+                    binary(node, left_expr, right_expr)
+                }
+                Infix::Conditional => {
+                    let then_expr = self.generate_ast(OperPrec::DefaultZero)?;
+                    self.check_paren(Token::Colon)?;
+                    let else_expr = self.parse_expr(right_bp)?;
+                    conditional(left_expr, then_expr, else_expr)
+                }
+                Infix::Custom(symbol) => {
+                    let right_expr = self.parse_expr(right_bp)?;
+                    binary_custom(symbol, left_expr, right_expr)
+                }
+            };
         }
         Ok(left_expr)
     }
 
     // Construct AST node for numbers, taking into account negative prefixes while handling parenthesis
-    fn parse_number(&mut self) -> Result<Node, ParseError> {
+    fn parse_number(&mut self) -> Result<Expr, ParseError> {
         let token = self.current_token.clone();
+        let start = self.current_span;
+        if let Some(op) = self.custom_operator(Fixity::Prefix) {
+            self.get_next_token()?;
+            let expr = self.generate_ast(op.prec)?;
+            return Ok(custom_node(op, vec![expr], start));
+        }
+        if let Some((prec, node)) = prefix_operator(&token) {
+            self.get_next_token()?;
+            let expr = self.generate_ast(prec)?;
+            let span = start.to(expr.span);
+            return Ok(Expr::new(node(Box::new(expr)), span));
+        }
         match token {
-            Token::Subtract => {
+            Token::Bool(b) => {
                 self.get_next_token()?;
-                let expr = self.generate_ast(OperPrec::Negative)?;
-                Ok(Node::Negative(Box::new(expr)))
+                Ok(Expr::new(Node::Bool(b), start))
             }
             Token::Num(i) => {
+                self.get_next_token()?;
+                Ok(Expr::new(Node::Number(i), start))
+            }
+            Token::Decimal(digits) => {
+                self.get_next_token()?;
+                Ok(Expr::new(Node::Decimal(digits), start))
+            }
+            Token::Int(i) => {
+                self.get_next_token()?;
+                Ok(Expr::new(Node::Integer(i), start))
+            }
+            Token::Ident(name) => {
                 self.get_next_token()?;
                 if self.current_token == Token::LeftParen {
-                    let right_expr = self.parse_number()?;
-                    return Ok(Node::Multiply(Box::new(Node::Number(i)), Box::new(right_expr)));
+                    return self.parse_call(name, start);
                 }
-                Ok(Node::Number(i))
+                Ok(Expr::new(Node::Variable(name), start))
             }
             Token::LeftParen => {
                 self.get_next_token()?;
-      
-                let expr = self.generate_ast(OperPrec::DefaultZero)?;
-                if let Err(e) = self.check_paren(Token::RightParen) {
-                    return Err(e)
-                }
+                let mut expr = self.generate_ast(OperPrec::DefaultZero)?;
+                let close = self.current_span;
+                self.check_paren(Token::RightParen)?;
+                expr.span = start.to(close);
+                Ok(expr)
+            }
+            _ => Err(ParseError::UnableToParse("Unable to parse".to_string(), start)),
+        }
+    }
 
-                // Check if there is another follow-up expression for multiplication
-                if self.current_token == Token::Subtract {
-                    return Ok(expr);
-                }
-                match self.parse_number() {
-                    Ok(right_expr) => Ok(Node::Multiply(Box::new(expr), Box::new(right_expr))),
-                    Err(_) => Ok(expr),
-                }
+    // Custom operator of the given fixity at the current token, if one is registered
+    fn custom_operator(&self, fixity: Fixity) -> Option<&'a Operator> {
+        let operators = self.operators;
+        match &self.current_token {
+            Token::Op(symbol) => operators.get(symbol, fixity),
+            _ => None,
+        }
+    }
+
+    // Construct a function call node from a comma separated argument list, e.g. max(1, x, 3)
+    fn parse_call(&mut self, name: String, start: Span) -> Result<Expr, ParseError> {
+        self.check_paren(Token::LeftParen)?;
+        let mut args = Vec::new();
+        if self.current_token != Token::RightParen {
+            args.push(self.generate_ast(OperPrec::DefaultZero)?);
+            while self.current_token == Token::Comma {
+                self.get_next_token()?;
+                args.push(self.generate_ast(OperPrec::DefaultZero)?);
             }
-            _ => Err(ParseError::UnableToParse("Unable to parse".to_string())),
         }
+        let span = start.to(self.current_span);
+        self.check_paren(Token::RightParen)?;
+
+        // if(cond, a, b) is the function form of cond ? a : b
+        if name == "if" {
+            let count = args.len();
+            let [cond, then_expr, else_expr]: [Expr; 3] = args.try_into().map_err(|_| {
+                ParseError::UnableToParse(format!("if expects 3 arguments, got {}", count), span)
+            })?;
+            return Ok(conditional(cond, then_expr, else_expr));
+        }
+        Ok(Expr::new(Node::Call(name, args), span))
     }
 
     // Check for balancing parenthesis
@@ -112,80 +448,75 @@ impl<'a> Parser<'a> {
             self.get_next_token()?;
             Ok(())
         } else {
-            Err(ParseError::InvalidOperator(format!(
-                "Expected {:?}, got {:?}",
-                expected, self.current_token
-            )))
+            Err(ParseError::InvalidOperator(
+                format!("Expected {}, got {}", expected, self.current_token),
+                self.current_span,
+            ))
         }
     }
+}
 
-    // Construct Operator AST nodes
-    fn convert_token_to_node(&mut self, left_expr: Node) -> Result<Node, ParseError> {
-        match self.current_token {
-            Token::Add => {
-                self.get_next_token()?; // Get right-side expression
-                let right_expr = self.generate_ast(OperPrec::AddSub)?;
-                Ok(Node::Add(Box::new(left_expr), Box::new(right_expr)))
-            },
-            Token::Subtract => {
-                self.get_next_token()?; // Get right-side expression
-                let right_expr = self.generate_ast(OperPrec::AddSub)?;
-                Ok(Node::Subtract(Box::new(left_expr), Box::new(right_expr)))
-            },
-            Token::Multiply => {
-                self.get_next_token()?; // Get right-side expression
-                let right_expr = self.generate_ast(OperPrec::MulDiv)?;
-                Ok(Node::Multiply(Box::new(left_expr), Box::new(right_expr)))
-            },
-            Token::Divide => {
-                self.get_next_token()?; // Get right-side expression
-                let right_expr = self.generate_ast(OperPrec::MulDiv)?;
-                Ok(Node::Divide(Box::new(left_expr), Box::new(right_expr)))
-            },
-            Token::Caret => {
-                self.get_next_token()?; // Get right-side expression
-                let right_expr = self.generate_ast(OperPrec::Power)?;
-                Ok(Node::Caret(Box::new(left_expr), Box::new(right_expr)))
-            },
-            Token::And => {
-                self.get_next_token()?; // Get right-side expression
-                let right_expr = self.generate_ast(OperPrec::AndOr)?;
-                Ok(Node::And(Box::new(left_expr), Box::new(right_expr)))
-            }
-            Token::Or => {
-                self.get_next_token()?; // Get right-side expression
-                let right_expr = self.generate_ast(OperPrec::AndOr)?;
-                Ok(Node::Or(Box::new(left_expr), Box::new(right_expr)))
-            }
-            _ => Err(ParseError::InvalidOperator(format!(
-                "Please enter valid operator {:?}",
-                self.current_token
-            ))),
-        }
-    }
+// Build a binary operator node spanning both of its operands
+fn binary(node: BinaryNode, left_expr: Expr, right_expr: Expr) -> Expr {
+    let span = left_expr.span.to(right_expr.span);
+    Expr::new(node(Box::new(left_expr), Box::new(right_expr)), span)
+}
+
+// Build the node of a prefix or postfix custom operator, spanning the operator and its operand
+fn custom_node(op: &Operator, operands: Vec<Expr>, op_span: Span) -> Expr {
+    let span = operands.iter().fold(op_span, |span, operand| span.to(operand.span));
+    Expr::new(Node::Operator(op.fixity, op.symbol.clone(), operands), span)
 }
 
-// Custom error handler for Parser
+// Build the node of an infix custom operator spanning both of its operands
+fn binary_custom(symbol: String, left_expr: Expr, right_expr: Expr) -> Expr {
+    let span = left_expr.span.to(right_expr.span);
+    Expr::new(Node::Operator(Fixity::Infix, symbol, vec![left_expr, right_expr]), span)
+}
+
+// Build a conditional node spanning the condition and both branches
+fn conditional(cond: Expr, then_expr: Expr, else_expr: Expr) -> Expr {
+    let span = cond.span.to(else_expr.span);
+    Expr::new(
+        Node::Conditional(Box::new(cond), Box::new(then_expr), Box::new(else_expr)),
+        span,
+    )
+}
+
+// Custom error handler for Parser, carrying the span of the offending input
 #[derive(Debug)]
 pub enum ParseError {
-    UnableToParse(String),
-    InvalidOperator(String),
+    UnableToParse(String, Span),
+    InvalidOperator(String, Span),
+    Lex(LexError),
+}
+
+impl ParseError {
+    // Location of the offending input in the source expression
+    pub fn span(&self) -> Span {
+        match self {
+            ParseError::UnableToParse(_, span) | ParseError::InvalidOperator(_, span) => *span,
+            ParseError::Lex(e) => e.span(),
+        }
+    }
 }
 
 impl fmt::Display for ParseError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match &self {
-            ParseError::UnableToParse(e) => write!(f, "Error in evaluating {}", e),
-            ParseError::InvalidOperator(e) => write!(f, "Error in evaluating {}", e),
+            ParseError::UnableToParse(e, _) | ParseError::InvalidOperator(e, _) => {
+                write!(f, "{}", e)
+            }
+            ParseError::Lex(e) => write!(f, "{}", e),
         }
     }
 }
 
-// Handle error thrown from AST module
+impl std::error::Error for ParseError {}
 
-impl From<Box<dyn std::error::Error>> for ParseError {
-    fn from(_evalerr: Box<dyn std::error::Error>) -> Self {
-        ParseError::UnableToParse("Unable to parse".into())
+impl From<LexError> for ParseError {
+    fn from(err: LexError) -> Self {
+        ParseError::Lex(err)
     }
 }
 
@@ -197,18 +528,23 @@ mod tests {
 
     use super::*;
     use crate::parsemath::ast::Node::*;
+
+    fn bx(node: Node) -> Box<Expr> {
+        Box::new(node.into())
+    }
+
     #[test]
     fn test_addition() {
         let mut parser = Parser::new("1+2").unwrap();
-        let expected = Add(Box::new(Number(1.0)), Box::new(Number(2.0)));
-        assert_eq!(parser.parse().unwrap(), expected);
+        let expected = Add(bx(Number(1.0)), bx(Number(2.0)));
+        assert_eq!(parser.parse().unwrap().node, expected);
     }
 
     #[test]
     fn test_bitwise_or() {
         let mut parser = Parser::new("6|2").unwrap();
-        let expected = Or(Box::new(Number(6.0)), Box::new(Number(2.0)));
-        assert_eq!(parser.parse().unwrap(), expected);
+        let expected = Or(bx(Number(6.0)), bx(Number(2.0)));
+        assert_eq!(parser.parse().unwrap().node, expected);
     }
 
     /* ~~~~~~~~~~~~ Additional test cases ~~~~~~~~~~~~ */
@@ -216,56 +552,124 @@ mod tests {
     fn test_parenthesis_ast() {
         let mut parser = Parser::new("(1 + 2) (3 / 4)").unwrap();
         let expected = Multiply(
-            Box::new(Add(
-                Box::new(Number(1.0)), 
-                Box::new(Number(2.0)))
+            bx(Add(
+                bx(Number(1.0)), 
+                bx(Number(2.0)))
             ), 
-            Box::new(Divide(
-                Box::new(Number(3.0)), 
-                Box::new(Number(4.0)))
+            bx(Divide(
+                bx(Number(3.0)), 
+                bx(Number(4.0)))
             )
         );
-        assert_eq!(parser.parse().unwrap(), expected);
+        assert_eq!(parser.parse().unwrap().node, expected);
     }
 
     #[test]
     fn test_parenthesis_multiplication() {
         let mut parser = Parser::new("(-5)4").unwrap();
         let mut expected = Multiply(
-            Box::new(Negative(Box::new(Number(5.0)))), 
-            Box::new(Number(4.0))
+            bx(Negative(bx(Number(5.0)))), 
+            bx(Number(4.0))
         );
-        assert_eq!(parser.parse().unwrap(), expected);
+        assert_eq!(parser.parse().unwrap().node, expected);
 
         parser = Parser::new("-5(4)").unwrap();
-        expected = Negative(Box::new(Multiply(
-            Box::new(Number(5.0)), 
-            Box::new(Number(4.0))
+        expected = Negative(bx(Multiply(
+            bx(Number(5.0)), 
+            bx(Number(4.0))
         )));
-        assert_eq!(parser.parse().unwrap(), expected);
+        assert_eq!(parser.parse().unwrap().node, expected);
     }
 
     #[test]
     fn test_bitwise_parsing() {
         let mut parser = Parser::new("6 & 3 | 1").unwrap();
         let expected = Or(
-            Box::new(And(
-                Box::new(Number(6.0)), 
-                Box::new(Number(3.0))
+            bx(And(
+                bx(Number(6.0)), 
+                bx(Number(3.0))
+            )),
+            bx(Number(1.0))
+        );
+        assert_eq!(parser.parse().unwrap().node, expected);
+    }
+
+    #[test]
+    fn test_bitwise_precedence() {
+        // 1 | 2 xor 3 & 4 << 1 + 1  ==  1 | (2 xor (3 & (4 << (1 + 1))))
+        let mut parser = Parser::new("1 | 2 xor 3 & 4 << 1 + 1").unwrap();
+        let expected = Or(
+            bx(Number(1.0)),
+            bx(Xor(
+                bx(Number(2.0)),
+                bx(And(
+                    bx(Number(3.0)),
+                    bx(ShiftLeft(bx(Number(4.0)), bx(Add(bx(Number(1.0)), bx(Number(1.0)))))),
+                )),
             )),
-            Box::new(Number(1.0))
         );
-        assert_eq!(parser.parse().unwrap(), expected);
+        assert_eq!(parser.parse().unwrap().node, expected);
+
+        parser = Parser::new("~6 & 3").unwrap();
+        let expected = And(bx(BitNot(bx(Number(6.0)))), bx(Number(3.0)));
+        assert_eq!(parser.parse().unwrap().node, expected);
+    }
+
+    #[test]
+    fn test_logical_precedence() {
+        // a || b && x < 1 + 2  ==  a || (b && (x < (1 + 2)))
+        let mut parser = Parser::new("a || b && x < 1 + 2").unwrap();
+        let expected = LogicalOr(
+            bx(Variable("a".into())),
+            bx(LogicalAnd(
+                bx(Variable("b".into())),
+                bx(Less(bx(Variable("x".into())), bx(Add(bx(Number(1.0)), bx(Number(2.0)))))),
+            )),
+        );
+        assert_eq!(parser.parse().unwrap().node, expected);
+
+        parser = Parser::new("!true != false").unwrap();
+        let expected = NotEqual(bx(Not(bx(Bool(true)))), bx(Bool(false)));
+        assert_eq!(parser.parse().unwrap().node, expected);
+    }
+
+    #[test]
+    fn test_conditional() {
+        // a ? 1 : b ? 2 : 3  ==  a ? 1 : (b ? 2 : 3)
+        let mut parser = Parser::new("a ? 1 : b ? 2 : 3").unwrap();
+        let expected = Conditional(
+            bx(Variable("a".into())),
+            bx(Number(1.0)),
+            bx(Conditional(bx(Variable("b".into())), bx(Number(2.0)), bx(Number(3.0)))),
+        );
+        assert_eq!(parser.parse().unwrap().node, expected);
+
+        parser = Parser::new("if(x > 0, 1, 2) + 1").unwrap();
+        let expected = Add(
+            bx(Conditional(
+                bx(Greater(bx(Variable("x".into())), bx(Number(0.0)))),
+                bx(Number(1.0)),
+                bx(Number(2.0)),
+            )),
+            bx(Number(1.0)),
+        );
+        assert_eq!(parser.parse().unwrap().node, expected);
+
+        parser = Parser::new("if(x, 1)").unwrap();
+        assert!(parser.parse().is_err());
+
+        parser = Parser::new("x ? 1").unwrap();
+        assert!(parser.parse().is_err());
     }
 
     #[test]
     fn test_unary_negative() {
         let mut parser = Parser::new("-3 + 5").unwrap();
         let expected = Add(
-            Box::new(Negative(Box::new(Number(3.0)))), 
-            Box::new(Number(5.0))
+            bx(Negative(bx(Number(3.0)))), 
+            bx(Number(5.0))
         );
-        assert_eq!(parser.parse().unwrap(), expected);
+        assert_eq!(parser.parse().unwrap().node, expected);
     }
 
     #[test]
@@ -276,4 +680,257 @@ mod tests {
         parser = Parser::new("8 @ 6").unwrap();
         assert!(parser.parse().is_err());
     }
+
+    #[test]
+    fn test_integer_literals() {
+        let mut parser = Parser::new("0xFF & 0b1010").unwrap();
+        let expected = And(bx(Integer(255)), bx(Integer(10)));
+        assert_eq!(parser.parse().unwrap().node, expected);
+    }
+
+    #[test]
+    fn test_variable() {
+        let mut parser = Parser::new("2 * x").unwrap();
+        let expected = Multiply(bx(Number(2.0)), bx(Variable("x".into())));
+        assert_eq!(parser.parse().unwrap().node, expected);
+    }
+
+    #[test]
+    fn test_function_call() {
+        let mut parser = Parser::new("max(1, x + 2) * sqrt(4)").unwrap();
+        let expected = Multiply(
+            bx(Call(
+                "max".into(),
+                vec![
+                    Number(1.0).into(),
+                    Add(bx(Variable("x".into())), bx(Number(2.0))).into(),
+                ],
+            )),
+            bx(Call("sqrt".into(), vec![Number(4.0).into()])),
+        );
+        assert_eq!(parser.parse().unwrap().node, expected);
+
+        parser = Parser::new("pi()").unwrap();
+        assert_eq!(parser.parse().unwrap().node, Call("pi".into(), vec![]));
+
+        parser = Parser::new("max(1, 2").unwrap();
+        assert!(parser.parse().is_err());
+    }
+
+    #[test]
+    fn test_modulo_parsing() {
+        let mut parser = Parser::new("1 + 7 % 3 * 2 // 4 rem 5 mod 6").unwrap();
+        let expected = Add(
+            bx(Number(1.0)),
+            bx(Modulo(
+                bx(Remainder(
+                    bx(FloorDivide(
+                        bx(Multiply(bx(Modulo(bx(Number(7.0)), bx(Number(3.0)))), bx(Number(2.0)))),
+                        bx(Number(4.0)),
+                    )),
+                    bx(Number(5.0)),
+                )),
+                bx(Number(6.0)),
+            )),
+        );
+        assert_eq!(parser.parse().unwrap().node, expected);
+    }
+
+    #[test]
+    fn test_node_spans() {
+        let ast = Parser::new("-3 + (4*5)").unwrap().parse().unwrap();
+        assert_eq!(ast.span, Span::new(0, 10));
+        match ast.node {
+            Add(left, right) => {
+                assert_eq!(left.span, Span::new(0, 2));
+                assert_eq!(right.span, Span::new(5, 10));
+            }
+            other => panic!("unexpected node {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_error_spans() {
+        let err = Parser::new("(1+2").unwrap().parse().unwrap_err();
+        assert_eq!(err.span(), Span::new(4, 4));
+
+        let err = Parser::new("8 @ 6").unwrap().parse().unwrap_err();
+        assert_eq!(err.span(), Span::new(2, 3));
+        assert!(matches!(err, ParseError::Lex(LexError::UnexpectedChar { ch: '@', .. })));
+
+        let err = Parser::new("(1+2))").unwrap().parse().unwrap_err();
+        assert_eq!(err.span(), Span::new(5, 6));
+    }
+
+    #[test]
+    fn test_adjacent_numbers() {
+        let err = Parser::new("12 34").unwrap().parse().unwrap_err();
+        assert_eq!(err.span(), Span::new(3, 5));
+
+        let options = ParseOptions { lenient_numbers: true, ..ParseOptions::default() };
+        let ast = Parser::with_options("12 34", options).unwrap().parse().unwrap();
+        assert_eq!(ast.node, Number(1234.0));
+    }
+
+    #[test]
+    fn test_associativity() {
+        let mut parser = Parser::new("2^3^2").unwrap();
+        let expected = Caret(bx(Number(2.0)), bx(Caret(bx(Number(3.0)), bx(Number(2.0)))));
+        assert_eq!(parser.parse().unwrap().node, expected);
+
+        let mut parser = Parser::new("8-4-2").unwrap();
+        let expected = Subtract(bx(Subtract(bx(Number(8.0)), bx(Number(4.0)))), bx(Number(2.0)));
+        assert_eq!(parser.parse().unwrap().node, expected);
+
+        // Prefix operators bind tighter than ^, also inside a right operand
+        let mut parser = Parser::new("-2^-3").unwrap();
+        let expected = Caret(bx(Negative(bx(Number(2.0)))), bx(Negative(bx(Number(3.0)))));
+        assert_eq!(parser.parse().unwrap().node, expected);
+    }
+
+    #[test]
+    fn test_custom_operators() {
+        use crate::parsemath::operators::Operator;
+        use crate::parsemath::token::Assoc;
+
+        let mut operators = OperatorTable::new();
+        operators.insert(Operator::infix("±", OperPrec::AddSub, Assoc::Left, |a, b| Some(a + b)));
+        operators.insert(Operator::infix("**", OperPrec::Power, Assoc::Right, |a, b| Some(a.powf(b))));
+        operators.insert(Operator::prefix("√", OperPrec::Negative, |a| Some(a.sqrt())));
+        operators.insert(Operator::postfix("°", OperPrec::Negative, |a| Some(a.to_radians())));
+        let op = |fixity, symbol: &str, operands: Vec<Node>| {
+            Operator(fixity, symbol.to_string(), operands.into_iter().map(Expr::from).collect())
+        };
+
+        let ast = Parser::with_operators("1 ± 2 * √4", ParseOptions::default(), &operators)
+            .unwrap()
+            .parse()
+            .unwrap();
+        let expected = op(
+            Fixity::Infix,
+            "±",
+            vec![Number(1.0), Multiply(bx(Number(2.0)), bx(op(Fixity::Prefix, "√", vec![Number(4.0)])))],
+        );
+        assert_eq!(ast.node, expected);
+
+        let ast = Parser::with_operators("2**3**2", ParseOptions::default(), &operators)
+            .unwrap()
+            .parse()
+            .unwrap();
+        let right = op(Fixity::Infix, "**", vec![Number(3.0), Number(2.0)]);
+        assert_eq!(ast.node, op(Fixity::Infix, "**", vec![Number(2.0), right]));
+
+        let ast = Parser::with_operators("-90°", ParseOptions::default(), &operators)
+            .unwrap()
+            .parse()
+            .unwrap();
+        assert_eq!(ast.node, op(Fixity::Postfix, "°", vec![Negative(bx(Number(90.0)))]));
+        assert_eq!(ast.span, Span::new(0, 5));
+
+        // Without the table the symbols are not recognized
+        assert!(Parser::new("1 ± 2").is_ok_and(|mut p| p.parse().is_err()));
+    }
+
+    #[test]
+    fn test_postfix_operators() {
+        let mut parser = Parser::new("3! + 20%").unwrap();
+        let expected = Add(bx(Factorial(bx(Number(3.0)))), bx(Percent(bx(Number(20.0)))));
+        assert_eq!(parser.parse().unwrap().node, expected);
+
+        // % is the modulo when an operand follows it
+        let mut parser = Parser::new("7 % (-2)").unwrap();
+        let expected = Modulo(bx(Number(7.0)), bx(Negative(bx(Number(2.0)))));
+        assert_eq!(parser.parse().unwrap().node, expected);
+        let mut parser = Parser::new("(50%) * 4").unwrap();
+        let expected = Multiply(bx(Percent(bx(Number(50.0)))), bx(Number(4.0)));
+        assert_eq!(parser.parse().unwrap().node, expected);
+        // but a percentage when an operator that is also infix follows it
+        let mut parser = Parser::new("20% - 5").unwrap();
+        let expected = Subtract(bx(Percent(bx(Number(20.0)))), bx(Number(5.0)));
+        assert_eq!(parser.parse().unwrap().node, expected);
+
+        // ! is the logical not in front of an operand
+        let mut parser = Parser::new("!(2! == 2)").unwrap();
+        let expected = Not(bx(Equal(bx(Factorial(bx(Number(2.0)))), bx(Number(2.0)))));
+        assert_eq!(parser.parse().unwrap().node, expected);
+
+        let ast = Parser::new("(3)!").unwrap().parse().unwrap();
+        assert_eq!(ast.node, Factorial(bx(Number(3.0))));
+        assert_eq!(ast.span, Span::new(0, 4));
+    }
+
+    #[test]
+    fn test_calculator_percent() {
+        let options = ParseOptions { calculator_percent: true, ..ParseOptions::default() };
+        let mut parser = Parser::with_options("100 - 10%", options).unwrap();
+        let expected = SubtractPercent(bx(Number(100.0)), bx(Percent(bx(Number(10.0)))));
+        assert_eq!(parser.parse().unwrap().node, expected);
+
+        let mut parser = Parser::new("100 - 10%").unwrap();
+        let expected = Subtract(bx(Number(100.0)), bx(Percent(bx(Number(10.0)))));
+        assert_eq!(parser.parse().unwrap().node, expected);
+    }
+
+    #[test]
+    fn test_unary_plus() {
+        let mut parser = Parser::new("+5 - +x").unwrap();
+        let expected =
+            Subtract(bx(Positive(bx(Number(5.0)))), bx(Positive(bx(Variable("x".into())))));
+        assert_eq!(parser.parse().unwrap().node, expected);
+    }
+
+    #[test]
+    fn test_implicit_multiplication() {
+        let parse = |expr| Parser::new(expr).unwrap().parse().map(|ast| ast.node);
+        let pi = || bx(Variable("pi".into()));
+
+        assert_eq!(parse("2pi").unwrap(), Multiply(bx(Number(2.0)), pi()));
+        assert_eq!(parse("(2)3").unwrap(), Multiply(bx(Number(2.0)), bx(Number(3.0))));
+        assert_eq!(
+            parse("3sqrt(2)").unwrap(),
+            Multiply(bx(Number(3.0)), bx(Call("sqrt".into(), vec![Number(2.0).into()])))
+        );
+        assert_eq!(
+            parse("(1+2)-3").unwrap(),
+            Subtract(bx(Add(bx(Number(1.0)), bx(Number(2.0)))), bx(Number(3.0)))
+        );
+        assert_eq!(
+            parse("-2pi^2").unwrap(),
+            Negative(bx(Multiply(bx(Number(2.0)), bx(Caret(pi(), bx(Number(2.0)))))))
+        );
+        assert_eq!(
+            parse("1/2pi").unwrap(),
+            Divide(bx(Number(1.0)), bx(Multiply(bx(Number(2.0)), pi())))
+        );
+        assert!(parse("2 3").is_err());
+        // The exponent does not extend into a product
+        let power = || bx(Caret(bx(Number(2.0)), bx(Number(3.0))));
+        assert_eq!(parse("2^3(4)").unwrap(), Multiply(power(), bx(Number(4.0))));
+        assert_eq!(parse("2^3pi").unwrap(), Multiply(power(), pi()));
+        assert_eq!(parse("2^(3)(4)").unwrap(), Multiply(power(), bx(Number(4.0))));
+        // An e without exponent digits is the constant or the start of a name
+        assert_eq!(parse("2e").unwrap(), Multiply(bx(Number(2.0)), bx(Variable("e".into()))));
+        assert_eq!(
+            parse("2exp(1)").unwrap(),
+            Multiply(bx(Number(2.0)), bx(Call("exp".into(), vec![Number(1.0).into()])))
+        );
+
+        let options = ParseOptions { implicit_multiplication: false, ..ParseOptions::default() };
+        for expr in ["2pi", "2(3)", "(2)(3)"] {
+            assert!(Parser::with_options(expr, options).unwrap().parse().is_err(), "{}", expr);
+        }
+    }
+
+    #[test]
+    fn test_assignment() {
+        let ast = Parser::new("x = y = 2").unwrap().parse().unwrap();
+        let expected = Assign("x".into(), bx(Assign("y".into(), bx(Number(2.0)))));
+        assert_eq!(ast.node, expected);
+        assert_eq!(ast.span, Span::new(0, 9));
+
+        // Only a name at the start of a statement can be assigned to
+        assert!(Parser::new("1 + x = 2").unwrap().parse().is_err());
+        assert!(Parser::new("(x = 2)").unwrap().parse().is_err());
+        assert!(Parser::new("x == 2").unwrap().parse().is_ok());
+    }
 }
@@ -0,0 +1,316 @@
+/// This module contains an exact rational number type, so that e.g. 1/3*3 evaluates to exactly 1.
+/// Numerator and denominator are i128, and results that outgrow them are reported as overflow.
+// Standard lib
+use std::cmp::Ordering;
+use std::fmt;
+
+// Internal modules
+use super::numeric::{self, NumError, Numeric, MAX_EXACT_F64};
+
+// Fraction in lowest terms with a positive denominator, e.g. evaluate_as::<Rational>("1/3").
+// Displays as 7/2, or as the mixed number 3 1/2 with the alternate flag {:#}.
+// Conversion to f64 is explicit, see to_f64.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Rational {
+    numer: i128,
+    denom: i128,
+}
+
+impl Rational {
+    // None for a zero denominator, or when the fraction does not fit after reducing it
+    pub fn new(numer: i128, denom: i128) -> Option<Self> {
+        if denom == 0 {
+            return None;
+        }
+        reduce(numer, denom).ok()
+    }
+
+    pub fn from_integer(value: i128) -> Self {
+        Rational { numer: value, denom: 1 }
+    }
+
+    pub fn numer(&self) -> i128 {
+        self.numer
+    }
+
+    pub fn denom(&self) -> i128 {
+        self.denom
+    }
+
+    pub fn is_integer(&self) -> bool {
+        self.denom == 1
+    }
+
+    // Nearest f64, which is no longer exact
+    pub fn to_f64(&self) -> f64 {
+        self.numer as f64 / self.denom as f64
+    }
+
+    // Integer part, rounded towards zero
+    pub fn trunc(&self) -> i128 {
+        self.numer / self.denom
+    }
+
+    pub fn floor(&self) -> i128 {
+        self.numer.div_euclid(self.denom)
+    }
+
+    fn recip(&self) -> Result<Rational, NumError> {
+        reduce(self.denom, self.numer)
+    }
+}
+
+// Bring a fraction with a non-zero denominator into lowest terms with a positive denominator
+fn reduce(numer: i128, denom: i128) -> Result<Rational, NumError> {
+    let divisor = i128::try_from(gcd(numer, denom)).map_err(|_| NumError::Overflow)?;
+    let (numer, denom) = (numer / divisor, denom / divisor);
+    if denom < 0 {
+        let numer = numer.checked_neg().ok_or(NumError::Overflow)?;
+        let denom = denom.checked_neg().ok_or(NumError::Overflow)?;
+        return Ok(Rational { numer, denom });
+    }
+    Ok(Rational { numer, denom })
+}
+
+fn gcd(a: i128, b: i128) -> u128 {
+    let (mut a, mut b) = (a.unsigned_abs(), b.unsigned_abs());
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a
+}
+
+// Multiply two i128 values, reporting overflow
+fn mul(a: i128, b: i128) -> Result<i128, NumError> {
+    a.checked_mul(b).ok_or(NumError::Overflow)
+}
+
+impl Ord for Rational {
+    fn cmp(&self, other: &Self) -> Ordering {
+        if let (Some(lhs), Some(rhs)) =
+            (self.numer.checked_mul(other.denom), other.numer.checked_mul(self.denom))
+        {
+            return lhs.cmp(&rhs);
+        }
+        // Cross products overflow: compare the integer parts, then the fractional parts
+        // through their reciprocals, which reverses the order
+        let (int1, int2) = (self.floor(), other.floor());
+        if int1 != int2 {
+            return int1.cmp(&int2);
+        }
+        let (rem1, rem2) = (self.numer.rem_euclid(self.denom), other.numer.rem_euclid(other.denom));
+        match (rem1, rem2) {
+            (0, 0) => Ordering::Equal,
+            (0, _) => Ordering::Less,
+            (_, 0) => Ordering::Greater,
+            _ => {
+                let frac1 = Rational { numer: self.denom, denom: rem1 };
+                let frac2 = Rational { numer: other.denom, denom: rem2 };
+                frac2.cmp(&frac1)
+            }
+        }
+    }
+}
+
+impl PartialOrd for Rational {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl fmt::Display for Rational {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.denom == 1 {
+            return write!(f, "{}", self.numer);
+        }
+        if f.alternate() && self.numer.unsigned_abs() > self.denom as u128 {
+            let rest = (self.numer % self.denom).unsigned_abs();
+            return write!(f, "{} {}/{}", self.trunc(), rest, self.denom);
+        }
+        write!(f, "{}/{}", self.numer, self.denom)
+    }
+}
+
+impl Numeric for Rational {
+    const NAME: &'static str = "rational";
+
+    // Goes through the shortest decimal representation, so that 0.1 becomes 1/10
+    // rather than the binary fraction nearest to it
+    fn from_f64(value: f64) -> Option<Self> {
+        if !value.is_finite() {
+            return None;
+        }
+        Rational::from_literal(&value.to_string())
+    }
+
+    fn from_literal(literal: &str) -> Option<Self> {
+        let (negative, digits, exponent) = numeric::split_literal(literal)?;
+        let digits: i128 = digits.parse().ok()?;
+        let scale = 10i128.checked_pow(exponent.unsigned_abs())?;
+        let value = match exponent {
+            0.. => Rational::from_integer(digits.checked_mul(scale)?),
+            _ => reduce(digits, scale).ok()?,
+        };
+        if negative { value.neg().ok() } else { Some(value) }
+    }
+
+    // Integers and results with at most 15 significant digits, like 0.25, are taken as exact.
+    // Results that need the full precision of f64, like sqrt(2), are rounded.
+    fn is_exact_result(value: f64) -> bool {
+        (value.fract() == 0.0 && value.abs() <= MAX_EXACT_F64)
+            || format!("{:.14e}", value).parse() == Ok(value)
+    }
+
+    fn from_u128(value: u128) -> Option<Self> {
+        i128::try_from(value).ok().map(Rational::from_integer)
+    }
+
+    fn to_f64(&self) -> f64 {
+        Rational::to_f64(self)
+    }
+
+    fn is_zero(&self) -> bool {
+        self.numer == 0
+    }
+
+    fn is_integer(&self) -> bool {
+        Rational::is_integer(self)
+    }
+
+    fn add(&self, rhs: &Self) -> Result<Self, NumError> {
+        // Scale by the least common multiple of the denominators to keep the terms small
+        let divisor = gcd(self.denom, rhs.denom) as i128;
+        let (lhs_scale, rhs_scale) = (rhs.denom / divisor, self.denom / divisor);
+        let numer = mul(self.numer, lhs_scale)?
+            .checked_add(mul(rhs.numer, rhs_scale)?)
+            .ok_or(NumError::Overflow)?;
+        reduce(numer, mul(self.denom, lhs_scale)?)
+    }
+
+    fn sub(&self, rhs: &Self) -> Result<Self, NumError> {
+        self.add(&rhs.neg()?)
+    }
+
+    fn mul(&self, rhs: &Self) -> Result<Self, NumError> {
+        // Cancel common factors crosswise before multiplying
+        let div1 = gcd(self.numer, rhs.denom).max(1) as i128;
+        let div2 = gcd(rhs.numer, self.denom).max(1) as i128;
+        let numer = mul(self.numer / div1, rhs.numer / div2)?;
+        let denom = mul(self.denom / div2, rhs.denom / div1)?;
+        reduce(numer, denom)
+    }
+
+    fn neg(&self) -> Result<Self, NumError> {
+        let numer = self.numer.checked_neg().ok_or(NumError::Overflow)?;
+        Ok(Rational { numer, denom: self.denom })
+    }
+
+    fn div(&self, rhs: &Self) -> Result<Self, NumError> {
+        self.mul(&rhs.recip()?)
+    }
+
+    fn rem(&self, rhs: &Self) -> Result<Self, NumError> {
+        let quotient = Rational::from_integer(self.div(rhs)?.trunc());
+        self.sub(&rhs.mul(&quotient)?)
+    }
+
+    // Only integral exponents have a rational result in general
+    fn pow(&self, exponent: &Self) -> Result<Self, NumError> {
+        if !exponent.is_integer() {
+            return Err(NumError::Inexact);
+        }
+        let exp = exponent.numer;
+        match (self.numer, self.denom) {
+            (0, _) if exp == 0 => return Ok(Rational::from_integer(1)),
+            (0, _) | (1, 1) => return Ok(*self),
+            (-1, 1) => return Ok(Rational::from_integer(if exp % 2 == 0 { 1 } else { -1 })),
+            _ => {}
+        }
+        let base = if exp < 0 { self.recip()? } else { *self };
+        let exp = u32::try_from(exp.unsigned_abs()).map_err(|_| NumError::Overflow)?;
+        // A fraction in lowest terms stays in lowest terms when raised to a power
+        let numer = base.numer.checked_pow(exp).ok_or(NumError::Overflow)?;
+        let denom = base.denom.checked_pow(exp).ok_or(NumError::Overflow)?;
+        Ok(Rational { numer, denom })
+    }
+
+    // Integral operands have a denominator of 1, so the numerators are combined as i128
+    fn bit_and(&self, rhs: &Self) -> Result<Self, NumError> {
+        self.numer.bit_and(&rhs.numer).map(Rational::from_integer)
+    }
+
+    fn bit_or(&self, rhs: &Self) -> Result<Self, NumError> {
+        self.numer.bit_or(&rhs.numer).map(Rational::from_integer)
+    }
+
+    fn bit_xor(&self, rhs: &Self) -> Result<Self, NumError> {
+        self.numer.bit_xor(&rhs.numer).map(Rational::from_integer)
+    }
+
+    fn bit_not(&self) -> Result<Self, NumError> {
+        self.numer.bit_not().map(Rational::from_integer)
+    }
+
+    fn shl(&self, amount: u32) -> Result<Self, NumError> {
+        self.numer.shl(amount).map(Rational::from_integer)
+    }
+
+    fn shr(&self, amount: u32) -> Result<Self, NumError> {
+        self.numer.shr(amount).map(Rational::from_integer)
+    }
+}
+
+// Unit tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ratio(numer: i128, denom: i128) -> Rational {
+        Rational::new(numer, denom).unwrap()
+    }
+
+    #[test]
+    fn test_lowest_terms() {
+        assert_eq!(ratio(6, -4), ratio(-3, 2));
+        assert_eq!((ratio(6, -4).numer(), ratio(6, -4).denom()), (-3, 2));
+        assert_eq!(Rational::new(1, 0), None);
+        assert_eq!(Rational::from_f64(0.1), Some(ratio(1, 10)));
+        assert_eq!(Rational::from_f64(-2.5), Some(ratio(-5, 2)));
+        assert_eq!(Rational::from_f64(f64::INFINITY), None);
+        assert!(Rational::is_exact_result(0.25) && Rational::is_exact_result(2f64.powi(50)));
+        assert!(!Rational::is_exact_result(2f64.sqrt()) && !Rational::is_exact_result(1.0 / 3.0));
+        let tiny = Rational::from_literal("0.1000000000000000000001").unwrap();
+        assert_eq!(tiny.sub(&ratio(1, 10)), Ok(ratio(1, 10i128.pow(22))));
+    }
+
+    #[test]
+    fn test_arithmetic() {
+        let third = ratio(1, 3);
+        assert_eq!(third.mul(&ratio(3, 1)), Ok(ratio(1, 1)));
+        assert_eq!(third.add(&ratio(1, 6)), Ok(ratio(1, 2)));
+        assert_eq!(ratio(-7, 2).floor_div(&ratio(1, 1)), Ok(ratio(-4, 1)));
+        assert_eq!(ratio(-7, 2).modulo(&ratio(2, 1)), Ok(ratio(1, 2)));
+        assert_eq!(ratio(-7, 2).rem(&ratio(2, 1)), Ok(ratio(-3, 2)));
+        assert_eq!(ratio(2, 3).pow(&ratio(-2, 1)), Ok(ratio(9, 4)));
+        assert_eq!(ratio(2, 1).pow(&ratio(1, 2)), Err(NumError::Inexact));
+        assert_eq!(ratio(1, i128::MAX).mul(&ratio(1, 2)), Err(NumError::Overflow));
+    }
+
+    #[test]
+    fn test_ordering() {
+        assert!(ratio(1, 3) < ratio(1, 2));
+        assert!(ratio(-1, 2) < ratio(-1, 3));
+        // Cross products overflow i128 here
+        assert!(ratio(i128::MAX - 1, i128::MAX) < ratio(i128::MAX, i128::MAX - 2));
+        assert!(ratio(i128::MAX - 2, i128::MAX - 1) < ratio(i128::MAX - 1, i128::MAX));
+    }
+
+    #[test]
+    fn test_display() {
+        assert_eq!(ratio(15, 4).to_string(), "15/4");
+        assert_eq!(format!("{:#}", ratio(15, 4)), "3 3/4");
+        assert_eq!(format!("{:#}", ratio(-7, 2)), "-3 1/2");
+        assert_eq!(format!("{:#}", ratio(1, 3)), "1/3");
+        assert_eq!(ratio(-8, 2).to_string(), "-4");
+    }
+}
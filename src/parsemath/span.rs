@@ -0,0 +1,76 @@
+/// This module holds the byte spans attached to tokens, AST nodes and errors,
+/// and renders them as rustc-style diagnostics pointing into the source expression.
+
+// Half-open byte range [start, end) into the source expression
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Span {
+    pub fn new(start: usize, end: usize) -> Self {
+        Span { start, end }
+    }
+
+    // Smallest span covering both self and other
+    pub fn to(self, other: Span) -> Span {
+        Span::new(self.start.min(other.start), self.end.max(other.end))
+    }
+}
+
+// Render the source line containing the span with a `^~~~` underline below it, e.g.
+//
+// error: Division by zero (1 / 0)
+//   |
+// 1 | 1/(3-3)
+//   |   ^~~~~
+pub fn render(source: &str, span: Span, message: &str) -> String {
+    let start = span.start.min(source.len());
+    let end = span.end.clamp(start, source.len());
+
+    let line_start = source[..start].rfind('\n').map_or(0, |i| i + 1);
+    let line_end = source[start..].find('\n').map_or(source.len(), |i| start + i);
+    let line_no = source[..start].matches('\n').count() + 1;
+    let line = source[line_start..line_end].trim_end_matches('\r');
+
+    // Columns are counted in chars so that multi-byte characters line up
+    let column = source[line_start..start].chars().count();
+    let width = source[start..end.min(line_end)].chars().count().max(1);
+
+    let gutter = " ".repeat(line_no.to_string().len());
+    format!(
+        "error: {}\n{} |\n{} | {}\n{} | {}^{}",
+        message,
+        gutter,
+        line_no,
+        line,
+        gutter,
+        " ".repeat(column),
+        "~".repeat(width - 1)
+    )
+}
+
+// Unit tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_underline() {
+        let out = render("1/(3-3)", Span::new(2, 7), "Division by zero");
+        assert_eq!(out, "error: Division by zero\n  |\n1 | 1/(3-3)\n  |   ^~~~~");
+    }
+
+    #[test]
+    fn test_render_end_of_input() {
+        let out = render("(1+2", Span::new(4, 4), "Expected ), got end of input");
+        assert_eq!(out, "error: Expected ), got end of input\n  |\n1 | (1+2\n  |     ^");
+    }
+
+    #[test]
+    fn test_render_second_line() {
+        let out = render("1+\n2 # 3", Span::new(5, 6), "Invalid character");
+        assert_eq!(out, "error: Invalid character\n  |\n2 | 2 # 3\n  |   ^");
+    }
+}
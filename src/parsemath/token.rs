@@ -1,4 +1,9 @@
 /// This contains enum for list of Tokens, and handles Operator precedence rules.
+// Standard lib
+use std::fmt;
+
+// Internal modules
+use super::span::Span;
 
 // List of valid tokens that can be constructed from arithmetic expression by Tokenizer
 
@@ -6,43 +11,133 @@
 pub enum Token {
     And,        // &
     Or,         // |
+    Xor,        // xor
+    Tilde,      // ~
+    Equal,      // ==
+    NotEqual,   // !=
+    Less,       // <
+    LessEqual,  // <=
+    Greater,    // >
+    GreaterEqual, // >=
+    LogicalAnd, // &&
+    LogicalOr,  // ||
+    Bang,       // ! (factorial after an operand, logical not before one)
+    ShiftLeft,  // <<
+    ShiftRight, // >>
     Add,        // +
     Subtract,   // -
     Multiply,   // *
     Divide,     // /
+    FloorDivide, // //
+    Percent,    // % (percent when no operand follows)
+    Mod,        // mod
+    Rem,        // rem
     Caret,      // ^
     LeftParen,  // (
     RightParen, // )
+    Comma,      // ,
+    Question,   // ?
+    Colon,      // :
+    Assign,     // =
     Num(f64),   // 12.34
+    Decimal(String), // 12.34 with its digits kept, see Tokenizer::exact_decimals
+    Int(u128),  // 0xFF, 0o755, 0b1010
+    Ident(String), // x, rate_2
+    Bool(bool), // true, false
+    Op(String), // custom operator registered at runtime, e.g. ±
     EOF,
 }
 
-// Order of operators as per operator precedence rules (low to high)
+// Token as it reads in the expression, for error messages, e.g. ) or end of input
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let symbol = match self {
+            Token::And => "&",
+            Token::Or => "|",
+            Token::Xor => "xor",
+            Token::Tilde => "~",
+            Token::Equal => "==",
+            Token::NotEqual => "!=",
+            Token::Less => "<",
+            Token::LessEqual => "<=",
+            Token::Greater => ">",
+            Token::GreaterEqual => ">=",
+            Token::LogicalAnd => "&&",
+            Token::LogicalOr => "||",
+            Token::Bang => "!",
+            Token::ShiftLeft => "<<",
+            Token::ShiftRight => ">>",
+            Token::Add => "+",
+            Token::Subtract => "-",
+            Token::Multiply => "*",
+            Token::Divide => "/",
+            Token::FloorDivide => "//",
+            Token::Percent => "%",
+            Token::Mod => "mod",
+            Token::Rem => "rem",
+            Token::Caret => "^",
+            Token::LeftParen => "(",
+            Token::RightParen => ")",
+            Token::Comma => ",",
+            Token::Question => "?",
+            Token::Colon => ":",
+            Token::Assign => "=",
+            Token::Num(n) => return write!(f, "{}", n),
+            Token::Int(n) => return write!(f, "{}", n),
+            Token::Bool(b) => return write!(f, "{}", b),
+            Token::Decimal(s) | Token::Ident(s) | Token::Op(s) => s,
+            Token::EOF => "end of input",
+        };
+        f.write_str(symbol)
+    }
+}
+
+// Token together with the location in the source expression it was read from
+#[derive(Debug, PartialEq, Clone)]
+pub struct SpannedToken {
+    pub token: Token,
+    pub span: Span,
+}
 
-#[derive(Debug, PartialEq, PartialOrd)]
+// Order of operators as per operator precedence rules (low to high).
+// The bitwise levels follow C/Rust: | binds loosest, then xor, then &, then the shifts.
+// Comparisons sit below all bitwise operators, the logical operators below those,
+// and the conditional operator binds loosest of all.
+// Prefix operators bind tighter than the infix ones, so -2^2 is (-2)^2,
+// and postfix operators tightest of all, so -3! is -(3!).
+// Implicit multiplication sits in between, so -2x is -(2*x).
+
+#[derive(Debug, PartialEq, PartialOrd, Clone, Copy)]
 /// Defines all the OperPrec levels, from lowest to highest.
 pub enum OperPrec {
     DefaultZero,
-    AndOr,
+    Conditional,
+    LogicalOr,
+    LogicalAnd,
+    Comparison,
+    BitOr,
+    BitXor,
+    BitAnd,
+    Shift,
     AddSub,
     MulDiv,
     Power,
     Negative,
+    Implicit,
+    Postfix,
 }
 
-// This contains methods to retrieve operator precedence for a given arithmetic operator
-
-impl Token {
-    pub fn get_oper_prec(&self) -> OperPrec {
-        use self::OperPrec::*;
-        use self::Token::*;
-        match *self {
-            And | Or => AndOr,
-            Add | Subtract => AddSub,
-            Multiply | Divide => MulDiv,
-            Caret => Power,
-
-            _ => DefaultZero,
-        }
+impl OperPrec {
+    // Binding power used by the parser. Levels are spaced two apart so that a
+    // right-associative operator can bind its right operand one step looser than itself.
+    pub fn binding_power(self) -> u8 {
+        self as u8 * 2
     }
 }
+
+// Associativity of an infix operator: 8-4-2 is (8-4)-2, while 2^3^2 is 2^(3^2)
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Assoc {
+    Left,
+    Right,
+}
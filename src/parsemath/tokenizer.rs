@@ -1,81 +1,427 @@
 /// This module reads characters in arithmetic expression and converts them to tokens.
 /// The allowed tokens are defined in ast module.
 // Standard lib
-use std::iter::Peekable;
-use std::str::Chars;
+use std::fmt;
+use std::iter::{FusedIterator, Peekable};
+use std::str::CharIndices;
 
 //Other internal modules
-use super::token::Token;
+use super::operators::{OperatorTable, NO_OPERATORS};
+use super::span::Span;
+use super::token::{SpannedToken, Token};
 
 // Other structs
 
-// Tokenizer struct contains a Peekable iterator on the arithmetic expression
+// Tokenizer struct contains a Peekable iterator on the arithmetic expression,
+// along with the byte offsets needed to attach a Span to every token
 pub struct Tokenizer<'a> {
-    expr: Peekable<Chars<'a>>,
+    source: &'a str,
+    expr: Peekable<CharIndices<'a>>,
+    len: usize,
+    lenient_numbers: bool,
+    exact_decimals: bool,
+    operators: &'a OperatorTable,
 }
 
 // Constructs a new instance of Tokenizer
 impl<'a> Tokenizer<'a> {
+    // Strict tokenizer: whitespace ends a number, so "12 34" is two numbers
     pub fn new(new_expr: &'a str) -> Self {
         Tokenizer {
-            expr: new_expr.chars().peekable(),
+            source: new_expr,
+            expr: new_expr.char_indices().peekable(),
+            len: new_expr.len(),
+            lenient_numbers: false,
+            exact_decimals: false,
+            operators: &NO_OPERATORS,
+        }
+    }
+
+    // Lenient tokenizer that skips whitespace inside numbers, so "1 2 . 3 4" reads as 12.34
+    pub fn lenient(new_expr: &'a str) -> Self {
+        Tokenizer { lenient_numbers: true, ..Tokenizer::new(new_expr) }
+    }
+
+    // Recognize the symbols of custom operators. They take priority over built-in tokens,
+    // so a registered "**" is not read as two multiplications.
+    pub fn with_operators(mut self, operators: &'a OperatorTable) -> Self {
+        self.operators = operators;
+        self
+    }
+
+    // Read decimal literals as Token::Decimal, keeping the digits as typed instead of the
+    // nearest f64, so that 0.1 can be evaluated exactly by a decimal number type
+    pub fn exact_decimals(mut self) -> Self {
+        self.exact_decimals = true;
+        self
+    }
+
+    // Empty span just past the last character, used to locate the end of input
+    pub fn end_span(&self) -> Span {
+        Span::new(self.len, self.len)
+    }
+
+    // Read a two-character operator such as && or <=, falling back to the single-character
+    // token when the second character does not follow
+    fn lex_pair(&mut self, span: &mut Span, second: char, pair: Token, single: Token) -> Token {
+        match self.expr.peek() {
+            Some(&(_, c)) if c == second => {
+                self.expr.next();
+                span.end += second.len_utf8();
+                pair
+            }
+            _ => single,
+        }
+    }
+
+    // Read the rest of an identifier whose first character has already been consumed
+    fn lex_ident(&mut self, first: char, start: usize) -> SpannedToken {
+        let mut name = String::from(first);
+        while let Some(&(_, c)) = self.expr.peek() {
+            if c.is_ascii_alphanumeric() || c == '_' {
+                name.push(c);
+                self.expr.next();
+            } else {
+                break;
+            }
+        }
+        let span = Span::new(start, start + name.len());
+        let token = match name.as_str() {
+            "mod" => Token::Mod,
+            "rem" => Token::Rem,
+            "xor" => Token::Xor,
+            "true" => Token::Bool(true),
+            "false" => Token::Bool(false),
+            _ => Token::Ident(name),
+        };
+        SpannedToken { token, span }
+    }
+
+    // Read the rest of a number whose first digit (or leading '.') has already been consumed
+    fn lex_number(&mut self, first: char, start: usize) -> Result<SpannedToken, LexError> {
+        if first == '0' {
+            let radix = match self.expr.peek() {
+                Some(&(_, 'x' | 'X')) => Some(16),
+                Some(&(_, 'o' | 'O')) => Some(8),
+                Some(&(_, 'b' | 'B')) => Some(2),
+                _ => None,
+            };
+            if let Some(radix) = radix {
+                self.expr.next();
+                return self.lex_radix_integer(radix, start);
+            }
+        }
+
+        // Underscores are dropped from the buffer once validated, e.g. 1_000.5e-3 -> 1000.5e-3
+        let mut buffer = String::from(first);
+        let mut has_decimal = first == '.';
+        let mut malformed = false;
+        let mut separator = DigitSeparator::new(10, first != '.');
+        let mut end = start + first.len_utf8();
+        let mut after_whitespace = false;
+        while let Some(&(i, c)) = self.expr.peek() {
+            match c {
+                '0'..='9' => buffer.push(c),
+                '_' => {}
+                '.' => {
+                    // Keep reading so the error covers the whole malformed literal
+                    malformed |= has_decimal;
+                    has_decimal = true;
+                    buffer.push(c);
+                }
+                c if c.is_ascii_whitespace() && self.lenient_numbers => {
+                    self.expr.next();
+                    after_whitespace = true;
+                    continue;
+                }
+                // The exponent must directly follow the mantissa, e.g. 6.022e23. Without digits
+                // the number ends before the 'e', so 2e and 2exp(1) are implicit products.
+                'e' | 'E' if !after_whitespace && self.exponent_follows() => {
+                    separator.check(c, i)?;
+                    self.expr.next();
+                    buffer.push(c);
+                    end = self.lex_exponent(&mut buffer, start, i + 1)?;
+                    break;
+                }
+                _ => break,
+            }
+            separator.check(c, i)?;
+            self.expr.next();
+            after_whitespace = false;
+            end = i + 1;
+        }
+        separator.finish()?;
+
+        let span = Span::new(start, end);
+        if malformed {
+            return Err(LexError::MultipleDecimalPoints { span });
+        }
+        match buffer.parse::<f64>() {
+            // Exact number types decide themselves whether the literal fits, even beyond f64
+            Ok(_) if self.exact_decimals => {
+                Ok(SpannedToken { token: Token::Decimal(buffer), span })
+            }
+            Ok(num) if num.is_finite() => Ok(SpannedToken { token: Token::Num(num), span }),
+            _ => Err(LexError::NumberOverflow { span }),
+        }
+    }
+
+    // Whether the 'e' at the current position starts an exponent, i.e. a digit follows it,
+    // optionally after a sign. A separator is misplaced there, so it also counts.
+    fn exponent_follows(&self) -> bool {
+        let mut ahead = self.expr.clone().skip(1);
+        let next = match ahead.next() {
+            Some((_, '+' | '-')) => ahead.next(),
+            next => next,
+        };
+        next.is_some_and(|(_, c)| c.is_ascii_digit() || c == '_')
+    }
+
+    // Read the exponent of a number in scientific notation, after the 'e' has been consumed.
+    // Returns the end offset of the literal.
+    fn lex_exponent(
+        &mut self,
+        buffer: &mut String,
+        start: usize,
+        mut end: usize,
+    ) -> Result<usize, LexError> {
+        if let Some(&(i, sign @ ('+' | '-'))) = self.expr.peek() {
+            buffer.push(sign);
+            self.expr.next();
+            end = i + 1;
+        }
+        let mut separator = DigitSeparator::new(10, false);
+        let mut has_digits = false;
+        while let Some(&(i, c)) = self.expr.peek() {
+            match c {
+                '0'..='9' => {
+                    buffer.push(c);
+                    has_digits = true;
+                }
+                '_' => {}
+                _ => break,
+            }
+            separator.check(c, i)?;
+            self.expr.next();
+            end = i + 1;
+        }
+        separator.finish()?;
+        if !has_digits {
+            return Err(LexError::MissingExponentDigits { span: Span::new(start, end) });
+        }
+        Ok(end)
+    }
+}
+
+// Checks that every '_' digit separator in a literal sits between two digits
+struct DigitSeparator {
+    radix: u32,
+    after_digit: bool,
+    pending: Option<usize>,
+}
+
+impl DigitSeparator {
+    fn new(radix: u32, after_digit: bool) -> Self {
+        DigitSeparator { radix, after_digit, pending: None }
+    }
+
+    // Letters count as digits in 0x/0o/0b literals, where bad digits are reported separately
+    fn is_digit(&self, c: char) -> bool {
+        match self.radix {
+            10 => c.is_ascii_digit(),
+            _ => c.is_ascii_alphanumeric(),
+        }
+    }
+
+    // Validate the character c at offset i against the one before it
+    fn check(&mut self, c: char, i: usize) -> Result<(), LexError> {
+        if c == '_' {
+            if !self.after_digit {
+                return Err(LexError::InvalidSeparator { span: Span::new(i, i + 1) });
+            }
+            self.pending = Some(i);
+        } else if let Some(pos) = self.pending.take() {
+            if !self.is_digit(c) {
+                return Err(LexError::InvalidSeparator { span: Span::new(pos, pos + 1) });
+            }
+        }
+        self.after_digit = self.is_digit(c);
+        Ok(())
+    }
+
+    // A literal cannot end with a separator
+    fn finish(&mut self) -> Result<(), LexError> {
+        match self.pending.take() {
+            Some(pos) => Err(LexError::InvalidSeparator { span: Span::new(pos, pos + 1) }),
+            None => Ok(()),
+        }
+    }
+}
+
+impl<'a> Tokenizer<'a> {
+    // Read the digits of a 0x, 0o or 0b literal whose prefix has already been consumed.
+    // The literal is parsed exactly into an integer rather than going through f64.
+    fn lex_radix_integer(&mut self, radix: u32, start: usize) -> Result<SpannedToken, LexError> {
+        let mut end = start + 2;
+        let mut value: Option<u128> = Some(0);
+        let mut invalid = None;
+        let mut has_digits = false;
+        let mut separator = DigitSeparator::new(radix, false);
+        while let Some(&(i, c)) = self.expr.peek() {
+            if !c.is_ascii_alphanumeric() && c != '_' {
+                break;
+            }
+            separator.check(c, i)?;
+            // Keep reading so the error covers the whole malformed literal
+            match c.to_digit(radix) {
+                _ if c == '_' => {}
+                Some(digit) => {
+                    has_digits = true;
+                    value = value
+                        .and_then(|v| v.checked_mul(radix as u128))
+                        .and_then(|v| v.checked_add(digit as u128));
+                }
+                None => {
+                    invalid.get_or_insert((c, Span::new(i, i + 1)));
+                }
+            }
+            self.expr.next();
+            end = i + 1;
+        }
+        separator.finish()?;
+
+        let span = Span::new(start, end);
+        if let Some((ch, span)) = invalid {
+            return Err(LexError::InvalidDigit { ch, radix, span });
+        }
+        if !has_digits {
+            return Err(LexError::MissingDigits { radix, span });
+        }
+        match value {
+            Some(value) => Ok(SpannedToken { token: Token::Int(value), span }),
+            None => Err(LexError::NumberOverflow { span }),
         }
     }
 }
 
 // Implement Iterator trait for Tokenizer struct.
-// With this, we can use next() method on tokenizer to retrieve the next token from arithmetic expression
+// With this, we can use next() method on tokenizer to retrieve the next token from arithmetic expression.
+// The iterator returns None once the end of the expression is reached, and keeps returning None after that.
 impl<'a> Iterator for Tokenizer<'a> {
-    type Item = Token;
+    type Item = Result<SpannedToken, LexError>;
 
-    fn next(&mut self) -> Option<Token> {
-        while let Some(&c) = self.expr.peek() {
+    fn next(&mut self) -> Option<Result<SpannedToken, LexError>> {
+        while let Some(&(_, c)) = self.expr.peek() {
             if c.is_ascii_whitespace() {
                 self.expr.next();
             } else {
                 break;
             }
         }
-        
-        let next_char = self.expr.next();
-        match next_char {
-            Some('0'..='9') => {
-                let mut buffer = String::from(next_char.unwrap());
-                let mut has_decimal = false;
-                while let Some(&c) = self.expr.peek() {
-                    match c {
-                        '0'..='9'=> {
-                            buffer.push(self.expr.next().unwrap());
-                        },
-                        '.' => {
-                            if has_decimal { 
-                                return None;
-                            } else {
-                                has_decimal = true;
-                                buffer.push(self.expr.next().unwrap());
-                            }
-                        },
-                        c if c.is_ascii_whitespace() => { self.expr.next(); },
-                        _ => break,
-                     }
-                }
-                buffer.parse::<f64>().map(Token::Num).ok()
+
+        let &(start, _) = self.expr.peek()?;
+        if let Some(symbol) = self.operators.match_symbol(&self.source[start..]) {
+            let span = Span::new(start, start + symbol.len());
+            for _ in symbol.chars() {
+                self.expr.next();
+            }
+            return Some(Ok(SpannedToken { token: Token::Op(symbol.to_string()), span }));
+        }
+
+        let (start, next_char) = self.expr.next()?;
+        let mut span = Span::new(start, start + next_char.len_utf8());
+        let token = match next_char {
+            '0'..='9' => return Some(self.lex_number(next_char, start)),
+            // Leading-dot literals such as .5
+            '.' if matches!(self.expr.peek(), Some(&(_, '0'..='9'))) => {
+                return Some(self.lex_number(next_char, start))
+            }
+            c if c.is_ascii_alphabetic() || c == '_' => return Some(Ok(self.lex_ident(c, start))),
+            '&' => self.lex_pair(&mut span, '&', Token::LogicalAnd, Token::And),
+            '|' => self.lex_pair(&mut span, '|', Token::LogicalOr, Token::Or),
+            '!' => self.lex_pair(&mut span, '=', Token::NotEqual, Token::Bang),
+            '+' => Token::Add,
+            '-' => Token::Subtract,
+            '*' => Token::Multiply,
+            '/' => self.lex_pair(&mut span, '/', Token::FloorDivide, Token::Divide),
+            '%' => Token::Percent,
+            '~' => Token::Tilde,
+            '<' => match self.expr.peek() {
+                Some(&(_, '<')) => self.lex_pair(&mut span, '<', Token::ShiftLeft, Token::Less),
+                _ => self.lex_pair(&mut span, '=', Token::LessEqual, Token::Less),
             },
-            Some('&') => Some(Token::And),
-            Some('|') => Some(Token::Or),
-            Some('+') => Some(Token::Add),
-            Some('-') => Some(Token::Subtract),
-            Some('*') => Some(Token::Multiply),
-            Some('/') => Some(Token::Divide),
-            Some('^') => Some(Token::Caret),
-            Some('(') => Some(Token::LeftParen),
-            Some(')') => Some(Token::RightParen),
-            None => Some(Token::EOF),
-            Some(_) => None,
+            '>' => match self.expr.peek() {
+                Some(&(_, '>')) => self.lex_pair(&mut span, '>', Token::ShiftRight, Token::Greater),
+                _ => self.lex_pair(&mut span, '=', Token::GreaterEqual, Token::Greater),
+            },
+            '=' => self.lex_pair(&mut span, '=', Token::Equal, Token::Assign),
+            '^' => Token::Caret,
+            '(' => Token::LeftParen,
+            ')' => Token::RightParen,
+            ',' => Token::Comma,
+            '?' => Token::Question,
+            ':' => Token::Colon,
+            c => return Some(Err(LexError::UnexpectedChar { ch: c, span })),
+        };
+        Some(Ok(SpannedToken { token, span }))
+    }
+}
+
+impl<'a> FusedIterator for Tokenizer<'a> {}
+
+// Custom error type for the Tokenizer, carrying the span of the offending characters
+#[derive(Debug, Clone, PartialEq)]
+pub enum LexError {
+    UnexpectedChar { ch: char, span: Span },
+    MultipleDecimalPoints { span: Span },
+    NumberOverflow { span: Span },
+    InvalidDigit { ch: char, radix: u32, span: Span },
+    MissingDigits { radix: u32, span: Span },
+    MissingExponentDigits { span: Span },
+    InvalidSeparator { span: Span },
+}
+
+impl LexError {
+    // Location of the offending characters in the source expression
+    pub fn span(&self) -> Span {
+        match self {
+            LexError::UnexpectedChar { span, .. }
+            | LexError::MultipleDecimalPoints { span }
+            | LexError::NumberOverflow { span }
+            | LexError::InvalidDigit { span, .. }
+            | LexError::MissingDigits { span, .. }
+            | LexError::MissingExponentDigits { span }
+            | LexError::InvalidSeparator { span } => *span,
+        }
+    }
+}
+
+impl fmt::Display for LexError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LexError::UnexpectedChar { ch, .. } => write!(f, "Unexpected character '{}'", ch),
+            LexError::MultipleDecimalPoints { .. } => {
+                write!(f, "Number has more than one decimal point")
+            }
+            LexError::NumberOverflow { .. } => write!(f, "Number is too large"),
+            LexError::InvalidDigit { ch, radix, .. } => {
+                write!(f, "Invalid digit '{}' in base {} literal", ch, radix)
+            }
+            LexError::MissingDigits { radix, .. } => {
+                write!(f, "Base {} literal has no digits", radix)
+            }
+            LexError::MissingExponentDigits { .. } => {
+                write!(f, "Exponent has no digits")
+            }
+            LexError::InvalidSeparator { .. } => {
+                write!(f, "Digit separator '_' must be between two digits")
+            }
         }
     }
 }
 
+impl std::error::Error for LexError {}
+
 // Unit tests
 #[cfg(test)]
 mod tests {
@@ -84,62 +430,310 @@ mod tests {
     #[test]
     fn test_positive_integer() {
         let mut tokenizer = Tokenizer::new("34");
-        assert_eq!(tokenizer.next().unwrap(), Token::Num(34.0))
+        assert_eq!(tokenizer.next().unwrap().unwrap().token, Token::Num(34.0))
     }
     #[test]
     fn test_decimal_number() {
         let mut tokenizer = Tokenizer::new("34.5");
-        assert_eq!(tokenizer.next().unwrap(), Token::Num(34.5))
+        assert_eq!(tokenizer.next().unwrap().unwrap().token, Token::Num(34.5))
     }
     #[test]
     fn test_invalid_char() {
         let mut tokenizer = Tokenizer::new("#$%");
-        assert_eq!(tokenizer.next(), None);
+        assert_eq!(
+            tokenizer.next(),
+            Some(Err(LexError::UnexpectedChar { ch: '#', span: Span::new(0, 1) }))
+        );
     }
 
     /* ~~~~~~~~~~~~ Additional test cases ~~~~~~~~~~~~ */
     #[test]
     fn test_incorrect_decimal() {
         let mut tokenizer = Tokenizer::new("12.3.4");
-        assert_eq!(tokenizer.next(), None);
+        assert_eq!(
+            tokenizer.next(),
+            Some(Err(LexError::MultipleDecimalPoints { span: Span::new(0, 6) }))
+        );
+    }
+
+    #[test]
+    fn test_number_overflow() {
+        let expr = "9".repeat(400);
+        let mut tokenizer = Tokenizer::new(&expr);
+        assert_eq!(
+            tokenizer.next(),
+            Some(Err(LexError::NumberOverflow { span: Span::new(0, 400) }))
+        );
     }
 
     #[test]
     fn test_space_in_number() {
-        let mut tokenizer = Tokenizer::new("1 2 . 3 4");
-        assert_eq!(tokenizer.next().unwrap(), Token::Num(12.34))
+        let mut tokenizer = Tokenizer::lenient("1 2 . 3 4");
+        assert_eq!(tokenizer.next().unwrap().unwrap().token, Token::Num(12.34))
+    }
+
+    #[test]
+    fn test_strict_space_ends_number() {
+        let tokens: Vec<Token> = Tokenizer::new("12 34\t.5").map(|t| t.unwrap().token).collect();
+        assert_eq!(tokens, vec![Token::Num(12.0), Token::Num(34.0), Token::Num(0.5)]);
+        assert!(matches!(
+            Tokenizer::new("1 . 2").nth(1),
+            Some(Err(LexError::UnexpectedChar { ch: '.', .. }))
+        ));
     }
 
     #[test]
     fn test_other_whitespaces() {
-        let mut tokenizer = Tokenizer::new("1\t.\n2\r\n3");
-        assert_eq!(tokenizer.next().unwrap(), Token::Num(1.23))
+        let mut tokenizer = Tokenizer::lenient("1\t.\n2\r\n3");
+        assert_eq!(tokenizer.next().unwrap().unwrap().token, Token::Num(1.23))
     }
 
     #[test]
     fn test_end_of_file() {
-        let mut tokenizer = Tokenizer::new("");
-        assert_eq!(tokenizer.next().unwrap(), Token::EOF)
+        let mut tokenizer = Tokenizer::new("1 ");
+        assert!(tokenizer.next().is_some());
+        assert_eq!(tokenizer.next(), None);
+        assert_eq!(tokenizer.next(), None);
+        assert_eq!(tokenizer.end_span(), Span::new(2, 2));
     }
 
     #[test]
     fn test_leading_whitespace() {
         let mut tokenizer = Tokenizer::new("  \n\r\t  34.56");
-        assert_eq!(tokenizer.next().unwrap(), Token::Num(34.56))
+        assert_eq!(tokenizer.next().unwrap().unwrap().token, Token::Num(34.56))
     }
 
     #[test]
     fn test_other_sign() {
-        let mut tokenizer = Tokenizer::new("&|+-*/^()");
-        assert_eq!(tokenizer.next().unwrap(), Token::And);
-        assert_eq!(tokenizer.next().unwrap(), Token::Or);
-        assert_eq!(tokenizer.next().unwrap(), Token::Add);
-        assert_eq!(tokenizer.next().unwrap(), Token::Subtract);
-        assert_eq!(tokenizer.next().unwrap(), Token::Multiply);
-        assert_eq!(tokenizer.next().unwrap(), Token::Divide);
-        assert_eq!(tokenizer.next().unwrap(), Token::Caret);
-        assert_eq!(tokenizer.next().unwrap(), Token::LeftParen);
-        assert_eq!(tokenizer.next().unwrap(), Token::RightParen);
+        let mut tokenizer = Tokenizer::new("&|+-*/^(),?:");
+        assert_eq!(tokenizer.next().unwrap().unwrap().token, Token::And);
+        assert_eq!(tokenizer.next().unwrap().unwrap().token, Token::Or);
+        assert_eq!(tokenizer.next().unwrap().unwrap().token, Token::Add);
+        assert_eq!(tokenizer.next().unwrap().unwrap().token, Token::Subtract);
+        assert_eq!(tokenizer.next().unwrap().unwrap().token, Token::Multiply);
+        assert_eq!(tokenizer.next().unwrap().unwrap().token, Token::Divide);
+        assert_eq!(tokenizer.next().unwrap().unwrap().token, Token::Caret);
+        assert_eq!(tokenizer.next().unwrap().unwrap().token, Token::LeftParen);
+        assert_eq!(tokenizer.next().unwrap().unwrap().token, Token::RightParen);
+        assert_eq!(tokenizer.next().unwrap().unwrap().token, Token::Comma);
+        assert_eq!(tokenizer.next().unwrap().unwrap().token, Token::Question);
+        assert_eq!(tokenizer.next().unwrap().unwrap().token, Token::Colon);
     }
 
+    #[test]
+    fn test_radix_literals() {
+        let tokens: Vec<Token> = Tokenizer::new("0xFF & 0b1010 | 0o755 + 0X1f")
+            .map(|t| t.unwrap().token)
+            .collect();
+        assert_eq!(
+            tokens,
+            vec![
+                Token::Int(255),
+                Token::And,
+                Token::Int(10),
+                Token::Or,
+                Token::Int(493),
+                Token::Add,
+                Token::Int(31),
+            ]
+        );
+
+        // Parsed exactly, beyond the 2^53 limit of f64
+        let mut tokenizer = Tokenizer::new("0x20000000000001");
+        assert_eq!(tokenizer.next().unwrap().unwrap().token, Token::Int((1 << 53) + 1));
+    }
+
+    #[test]
+    fn test_radix_literal_errors() {
+        assert_eq!(
+            Tokenizer::new("0b102").next(),
+            Some(Err(LexError::InvalidDigit { ch: '2', radix: 2, span: Span::new(4, 5) }))
+        );
+        assert_eq!(
+            Tokenizer::new("0o78").next(),
+            Some(Err(LexError::InvalidDigit { ch: '8', radix: 8, span: Span::new(3, 4) }))
+        );
+        assert_eq!(
+            Tokenizer::new("0x + 1").next(),
+            Some(Err(LexError::MissingDigits { radix: 16, span: Span::new(0, 2) }))
+        );
+        let expr = format!("0x{}", "F".repeat(33));
+        assert_eq!(
+            Tokenizer::new(&expr).next(),
+            Some(Err(LexError::NumberOverflow { span: Span::new(0, 35) }))
+        );
+    }
+
+    #[test]
+    fn test_scientific_notation() {
+        let token = |expr| Tokenizer::new(expr).next().unwrap().unwrap().token;
+        assert_eq!(token("6.022e23"), Token::Num(6.022e23));
+        assert_eq!(token("1E-9"), Token::Num(1e-9));
+        assert_eq!(token("2.5e+3"), Token::Num(2500.0));
+        assert_eq!(token(".5"), Token::Num(0.5));
+        assert_eq!(token(".25e1"), Token::Num(2.5));
+        // Without exponent digits the number ends before the 'e'
+        let tokens = |expr| -> Vec<Token> {
+            Tokenizer::new(expr).map(|token| token.unwrap().token).collect()
+        };
+        assert_eq!(tokens("2e"), [Token::Num(2.0), Token::Ident("e".into())]);
+        assert_eq!(tokens("1e-"), [Token::Num(1.0), Token::Ident("e".into()), Token::Subtract]);
+        assert_eq!(
+            tokens("2exp(1)"),
+            [
+                Token::Num(2.0),
+                Token::Ident("exp".into()),
+                Token::LeftParen,
+                Token::Num(1.0),
+                Token::RightParen
+            ]
+        );
+        assert_eq!(
+            Tokenizer::new("1e999").next(),
+            Some(Err(LexError::NumberOverflow { span: Span::new(0, 5) }))
+        );
+        assert!(matches!(
+            Tokenizer::new(".").next(),
+            Some(Err(LexError::UnexpectedChar { ch: '.', .. }))
+        ));
+    }
+
+    #[test]
+    fn test_digit_separators() {
+        let token = |expr| Tokenizer::new(expr).next().unwrap().unwrap().token;
+        assert_eq!(token("1_000_000"), Token::Num(1_000_000.0));
+        assert_eq!(token("12.345_678"), Token::Num(12.345_678));
+        assert_eq!(token("1e1_0"), Token::Num(1e10));
+        assert_eq!(token("0xFF_FF"), Token::Int(0xFFFF));
+        assert_eq!(token("0b1010_0101"), Token::Int(0b1010_0101));
+
+        for (expr, at) in [("1__0", 2), ("1_", 1), ("1_.5", 1), ("1._5", 2), ("1_e5", 1), ("1e_5", 2), ("0x_F", 2)] {
+            assert_eq!(
+                Tokenizer::new(expr).next(),
+                Some(Err(LexError::InvalidSeparator { span: Span::new(at, at + 1) })),
+                "{}",
+                expr
+            );
+        }
+    }
+
+    #[test]
+    fn test_exact_decimals() {
+        let token = |expr| Tokenizer::new(expr).exact_decimals().next().unwrap().unwrap().token;
+        assert_eq!(token("0.10"), Token::Decimal("0.10".into()));
+        assert_eq!(token("1_000.000_1"), Token::Decimal("1000.0001".into()));
+        assert_eq!(token(".5e-3"), Token::Decimal(".5e-3".into()));
+        assert_eq!(token("0xFF"), Token::Int(0xFF));
+        assert_eq!(token("1e400"), Token::Decimal("1e400".into()));
+        assert_eq!(token(&"9".repeat(400)), Token::Decimal("9".repeat(400)));
+        let mut tokenizer = Tokenizer::lenient("1 000.5").exact_decimals();
+        assert_eq!(tokenizer.next().unwrap().unwrap().token, Token::Decimal("1000.5".into()));
+    }
+
+    #[test]
+    fn test_identifier() {
+        let mut tokenizer = Tokenizer::new("rate_2*x");
+        let token = tokenizer.next().unwrap().unwrap();
+        assert_eq!(token.token, Token::Ident("rate_2".into()));
+        assert_eq!(token.span, Span::new(0, 6));
+        assert_eq!(tokenizer.next().unwrap().unwrap().token, Token::Multiply);
+        assert_eq!(tokenizer.next().unwrap().unwrap().token, Token::Ident("x".into()));
+    }
+
+    #[test]
+    fn test_modulo_operators() {
+        let tokens: Vec<Token> = Tokenizer::new("7 % 2 // 1 / 3 mod 4 rem 5 model")
+            .map(|t| t.unwrap().token)
+            .collect();
+        assert_eq!(
+            tokens,
+            vec![
+                Token::Num(7.0),
+                Token::Percent,
+                Token::Num(2.0),
+                Token::FloorDivide,
+                Token::Num(1.0),
+                Token::Divide,
+                Token::Num(3.0),
+                Token::Mod,
+                Token::Num(4.0),
+                Token::Rem,
+                Token::Num(5.0),
+                Token::Ident("model".into()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_bitwise_operators() {
+        let tokens: Vec<Token> = Tokenizer::new("~1 << 2 >> 3 xor 4")
+            .map(|t| t.unwrap().token)
+            .collect();
+        assert_eq!(
+            tokens,
+            vec![
+                Token::Tilde,
+                Token::Num(1.0),
+                Token::ShiftLeft,
+                Token::Num(2.0),
+                Token::ShiftRight,
+                Token::Num(3.0),
+                Token::Xor,
+                Token::Num(4.0),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_comparison_and_logic() {
+        let tokens: Vec<Token> = Tokenizer::new("== != < <= > >= && || ! & | true false")
+            .map(|t| t.unwrap().token)
+            .collect();
+        assert_eq!(
+            tokens,
+            vec![
+                Token::Equal,
+                Token::NotEqual,
+                Token::Less,
+                Token::LessEqual,
+                Token::Greater,
+                Token::GreaterEqual,
+                Token::LogicalAnd,
+                Token::LogicalOr,
+                Token::Bang,
+                Token::And,
+                Token::Or,
+                Token::Bool(true),
+                Token::Bool(false),
+            ]
+        );
+        assert_eq!(Tokenizer::new("x = 2").nth(1).unwrap().unwrap().token, Token::Assign);
+    }
+
+    #[test]
+    fn test_token_spans() {
+        let tokenizer = Tokenizer::new(" 12.5 *(3)");
+        let spans: Vec<Span> = tokenizer.map(|t| t.unwrap().span).collect();
+        assert_eq!(
+            spans,
+            vec![
+                Span::new(1, 5),
+                Span::new(6, 7),
+                Span::new(7, 8),
+                Span::new(8, 9),
+                Span::new(9, 10),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_invalid_char_span() {
+        let mut tokenizer = Tokenizer::new("1 + é");
+        tokenizer.next();
+        tokenizer.next();
+        assert_eq!(
+            tokenizer.next(),
+            Some(Err(LexError::UnexpectedChar { ch: 'é', span: Span::new(4, 6) }))
+        );
+    }
 }
@@ -0,0 +1,85 @@
+/// This module contains the Value type produced by evaluating an expression.
+// Standard lib
+use std::fmt;
+
+// Result of evaluating an expression: a number, or a boolean from a comparison or logical operator.
+// Numbers are f64 unless the expression is evaluated with another number type, e.g. Value<i64>.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Value<N = f64> {
+    Number(N),
+    Bool(bool),
+}
+
+impl<N> Value<N> {
+    pub fn as_number(self) -> Option<N> {
+        match self {
+            Value::Number(n) => Some(n),
+            Value::Bool(_) => None,
+        }
+    }
+
+    pub fn as_bool(self) -> Option<bool> {
+        match self {
+            Value::Bool(b) => Some(b),
+            Value::Number(_) => None,
+        }
+    }
+
+    // Name of the value's type, used in type errors
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Value::Number(_) => "number",
+            Value::Bool(_) => "bool",
+        }
+    }
+}
+
+impl From<f64> for Value {
+    fn from(n: f64) -> Self {
+        Value::Number(n)
+    }
+}
+
+impl From<bool> for Value {
+    fn from(b: bool) -> Self {
+        Value::Bool(b)
+    }
+}
+
+// Allows comparing a result directly against a number, e.g. assert_eq!(value, 3.75)
+impl<N: PartialEq> PartialEq<N> for Value<N> {
+    fn eq(&self, other: &N) -> bool {
+        matches!(self, Value::Number(n) if n == other)
+    }
+}
+
+impl PartialEq<bool> for Value {
+    fn eq(&self, other: &bool) -> bool {
+        *self == Value::Bool(*other)
+    }
+}
+
+impl<N: fmt::Display> fmt::Display for Value<N> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            // Forward the formatter so that flags such as {:#} reach the number type
+            Value::Number(n) => n.fmt(f),
+            Value::Bool(b) => write!(f, "{}", b),
+        }
+    }
+}
+
+// Unit tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_conversions() {
+        assert_eq!(Value::from(2.5).as_number(), Some(2.5));
+        assert_eq!(Value::from(true).as_bool(), Some(true));
+        assert_eq!(Value::from(true).as_number(), None);
+        assert_eq!(Value::Number(1.0), 1.0);
+        assert_eq!(Value::from(false).to_string(), "false");
+    }
+}
@@ -1,5 +1,14 @@
 use expression_eval::parsemath::ast;
 use expression_eval::parsemath::parser::Parser;
+use expression_eval::parsemath::rational::Rational;
+use expression_eval::parsemath::ast::{EvalError, Node};
+use expression_eval::parsemath::context::Context;
+use expression_eval::parsemath::decimal::{Decimal, HalfEven, HalfUp, Truncate};
+use expression_eval::parsemath::numeric::{Evaluated, NumError, Numeric};
+use expression_eval::parsemath::operators::Operator;
+use expression_eval::parsemath::token::{Assoc, OperPrec};
+use expression_eval::parsemath::value::Value;
+use expression_eval::{Error, Evaluator};
 
 #[test]
 fn test_expr1() {
@@ -54,4 +63,354 @@ fn test_expr7() {
 fn test_expr8() {
     let mut parser = Parser::new("(6 + 8) $% 45 #* 90").unwrap();
     assert!(parser.parse().is_err());
-}
\ No newline at end of file
+}
+#[test]
+fn test_evaluate_facade() {
+    assert_eq!(expression_eval::evaluate("(3.5 + 4.5) * 2").unwrap(), 16.0);
+    assert!(expression_eval::evaluate("1 / 0").is_err());
+    assert!(expression_eval::evaluate("(1 + 2").is_err());
+    assert_eq!(expression_eval::evaluate("2 ^ 3 ^ 2").unwrap(), 512.0);
+}
+
+#[test]
+fn test_parse_facade() {
+    let ast = expression_eval::parse("1+2").unwrap();
+    assert_eq!(
+        ast.node,
+        Node::Add(Box::new(Node::Number(1.0).into()), Box::new(Node::Number(2.0).into()))
+    );
+}
+
+#[test]
+fn test_error_kinds() {
+    assert!(matches!(
+        expression_eval::evaluate("2 / (3 - 3)"),
+        Err(Error::Eval(EvalError::DivisionByZero { .. }))
+    ));
+    assert!(matches!(expression_eval::evaluate("2 / (3 - 3"), Err(Error::Parse(_))));
+}
+
+#[test]
+fn test_render_diagnostic() {
+    let source = "(4 + 1) * (2 / (3 - 3))";
+    let err = expression_eval::evaluate(source).unwrap_err();
+    assert_eq!(
+        err.render(source),
+        "error: Division by zero (2 / 0)\n  |\n1 | (4 + 1) * (2 / (3 - 3))\n  |           ^~~~~~~~~~~~~"
+    );
+
+    let source = "(6 + 8) $ 45";
+    let err = expression_eval::evaluate(source).unwrap_err();
+    assert_eq!(
+        err.render(source),
+        "error: Unexpected character '$'\n  |\n1 | (6 + 8) $ 45\n  |         ^"
+    );
+
+    let source = "(1 + 2";
+    let err = expression_eval::evaluate(source).unwrap_err();
+    assert_eq!(
+        err.render(source),
+        "error: Expected ), got end of input\n  |\n1 | (1 + 2\n  |       ^"
+    );
+
+    let source = "2 3";
+    let err = expression_eval::evaluate(source).unwrap_err();
+    assert_eq!(err.to_string(), "Unexpected 3");
+}
+
+#[test]
+fn test_evaluate_with_context() {
+    let ctx = Context::new().with_var("price", 12.5).with_var("qty", 4.0);
+    assert_eq!(expression_eval::evaluate_with("price * qty - 5", &ctx).unwrap(), 45.0);
+    assert!(matches!(
+        expression_eval::evaluate_with("price * discount", &ctx),
+        Err(Error::Eval(EvalError::UnknownVariable { .. }))
+    ));
+}
+
+#[test]
+fn test_builtin_functions() {
+    let value = expression_eval::evaluate("hypot(3, 4) * log(100) + floor(2.7)").unwrap();
+    assert_eq!(value, 12.0);
+    assert!(matches!(
+        expression_eval::evaluate("2 * sqrt(1 - 5)"),
+        Err(Error::Eval(EvalError::DomainError { .. }))
+    ));
+}
+
+#[test]
+fn test_evaluator_host_functions() {
+    let evaluator = Evaluator::new()
+        .with_var("qty", 3.0)
+        .register_fn("unit_price", 1, |a| if a[0] > 0.0 { Some(a[0] * 2.5) } else { None });
+    assert_eq!(evaluator.evaluate("qty * unit_price(4)").unwrap(), 30.0);
+    assert!(evaluator.evaluate("unit_price(-1)").is_err());
+}
+
+#[test]
+fn test_evaluator_custom_operators() {
+    let evaluator = Evaluator::new()
+        .with_var("floor", 40.0)
+        .register_operator(Operator::infix("max=", OperPrec::Comparison, Assoc::Left, |a, b| {
+            Some(a.max(b))
+        }))
+        .register_operator(Operator::prefix("√", OperPrec::Negative, |a| {
+            if a >= 0.0 { Some(a.sqrt()) } else { None }
+        }));
+    assert_eq!(evaluator.evaluate("floor max= 10 * 5").unwrap(), 50.0);
+    assert_eq!(evaluator.evaluate("√16 + 1").unwrap(), 5.0);
+    assert!(matches!(
+        evaluator.evaluate("√(0 - 4)"),
+        Err(Error::Eval(EvalError::DomainError { .. }))
+    ));
+    assert!(matches!(expression_eval::evaluate("√16"), Err(Error::Parse(_))));
+}
+
+#[test]
+fn test_business_rule() {
+    let evaluator = Evaluator::new().with_var("qty", 12.0).with_var("member", true);
+    assert_eq!(evaluator.evaluate("qty > 10 && member").unwrap(), true);
+    assert_eq!(evaluator.evaluate("qty >= 20 || !member").unwrap(), false);
+    assert!(matches!(
+        evaluator.evaluate("member * 2"),
+        Err(Error::Eval(EvalError::TypeMismatch { expected: "number", found: "bool", .. }))
+    ));
+}
+
+#[test]
+fn test_conditional_rule() {
+    let evaluator = Evaluator::new().with_var("qty", 0.0);
+    assert_eq!(evaluator.evaluate("qty != 0 ? 100 / qty : 0").unwrap(), 0.0);
+    assert_eq!(evaluator.evaluate("if(qty > 10, 5, 0) + 1").unwrap(), 1.0);
+}
+
+#[test]
+fn test_exact_integer_mode() {
+    let evaluator = Evaluator::new().with_var("n", 3.0);
+    assert_eq!(evaluator.evaluate("2^60 + 1").unwrap(), 2f64.powi(60));
+    assert_eq!(evaluator.evaluate_as::<i64>("2^60 + n").unwrap(), Value::Number((1 << 60) + 3));
+    assert!(matches!(
+        evaluator.evaluate_as::<i64>("2^62 * 2"),
+        Err(Error::Eval(EvalError::NumericOverflow { op: "*", ty: "i64", .. }))
+    ));
+    assert!(matches!(
+        evaluator.evaluate_as::<i128>("7 / 2"),
+        Err(Error::Eval(EvalError::InexactResult { op: "/", .. }))
+    ));
+    assert_eq!(
+        evaluator.evaluate_promoting::<i128>("8 / 2 + n").unwrap(),
+        Evaluated::Exact(Value::Number(7))
+    );
+    assert_eq!(
+        evaluator.evaluate_promoting::<i128>("7 / 2 + n").unwrap(),
+        Evaluated::Promoted(Value::Number(6.5))
+    );
+    assert_eq!(expression_eval::evaluate_as::<i64>("10! / 2").unwrap(), 1_814_400);
+}
+
+#[cfg(feature = "bignum")]
+#[test]
+fn test_bignum_mode() {
+    use expression_eval::parsemath::bignum::BigInteger;
+
+    let evaluator = Evaluator::new().with_var("n", 100.0);
+    let value = evaluator.evaluate_as::<BigInteger>("n! / (n - 2)! + 2^521 - 2^521").unwrap();
+    assert_eq!(value.to_string(), "9900");
+    assert!(matches!(
+        evaluator.evaluate_as::<BigInteger<50>>("n!"),
+        Err(Error::Eval(EvalError::NumericOverflow { op: "!", .. }))
+    ));
+    // Literals beyond the range of f64 stay exact
+    let huge = format!("{} + 1", "9".repeat(320));
+    let value = evaluator.evaluate_as::<BigInteger>(&huge).unwrap();
+    assert_eq!(value.to_string(), format!("1{}", "0".repeat(320)));
+    let value = evaluator.evaluate_as::<BigInteger>("1e400 / 1e399").unwrap();
+    assert_eq!(value.to_string(), "10");
+}
+
+#[test]
+fn test_rational_mode() {
+    let evaluator = Evaluator::new();
+    let value = evaluator.evaluate_as::<Rational>("3+2-1*5/4").unwrap();
+    assert_eq!(value.to_string(), "15/4");
+    assert_eq!(format!("{:#}", value), "3 3/4");
+    assert_eq!(value.as_number().unwrap().to_f64(), 3.75);
+    assert_eq!(evaluator.evaluate_as::<Rational>("1/3*3 == 1").unwrap(), Value::Bool(true));
+    assert_eq!(evaluator.evaluate_as::<Rational>("0.1 + 0.2").unwrap().to_string(), "3/10");
+    assert!(matches!(
+        evaluator.evaluate_as::<Rational>("2^0.5"),
+        Err(Error::Eval(EvalError::InexactResult { op: "^", ty: "rational", .. }))
+    ));
+    // Functions are computed in f64, so only results that are not rounded stay exact
+    assert_eq!(evaluator.evaluate_as::<Rational>("sqrt(16) / 8").unwrap().to_string(), "1/2");
+    assert!(matches!(
+        evaluator.evaluate_as::<Rational>("sqrt(2)"),
+        Err(Error::Eval(EvalError::InexactResult { op: "a function call", .. }))
+    ));
+    let value = evaluator.evaluate_promoting::<Rational>("sqrt(2)").unwrap();
+    assert_eq!(value, Evaluated::Promoted(Value::Number(2f64.sqrt())));
+    assert!(matches!(
+        evaluator.evaluate_as::<Rational>("1/2 & 1"),
+        Err(Error::Eval(EvalError::NonIntegerBitwiseOperand { op: "&", value, .. })) if value == 0.5
+    ));
+}
+
+#[test]
+fn test_decimal_mode() {
+    let evaluator = Evaluator::new().with_var("rate", 0.07);
+    assert_eq!(evaluator.evaluate("0.1 + 0.2").unwrap(), 0.30000000000000004);
+    let value = evaluator.evaluate_as::<Decimal>("0.1 + 0.2").unwrap();
+    assert_eq!(value.to_string(), "0.3");
+    assert_eq!(evaluator.evaluate_as::<Decimal>("0.1 + 0.2 == 0.3").unwrap(), Value::Bool(true));
+
+    // Digits beyond f64 precision survive tokenizing
+    let value = evaluator.evaluate_as::<Decimal<24>>("0.100000000000000000000001 - 0.1").unwrap();
+    assert_eq!(value.to_string(), "0.000000000000000000000001");
+
+    let price = "19.99 * 3 * (1 + rate)";
+    assert_eq!(evaluator.evaluate_as::<Decimal<2, HalfUp>>(price).unwrap().to_string(), "64.17");
+    assert_eq!(evaluator.evaluate_as::<Decimal<2, Truncate>>(price).unwrap().to_string(), "64.16");
+    let split = evaluator.evaluate_as::<Decimal<2, HalfEven>>("100 / 3").unwrap();
+    assert_eq!(format!("{:.2}", split), "33.33");
+
+    // 100 does not fit into 38 digits with 37 of them after the point
+    assert!(matches!(
+        evaluator.evaluate_as::<Decimal<37>>("5%"),
+        Err(Error::Eval(EvalError::NotRepresentable { value: 100.0, ty: "decimal", .. }))
+    ));
+}
+
+// The same expressions must give the same results with every number type
+fn check_shared_suite<N: Numeric>() {
+    let evaluator = Evaluator::new().calculator_percent().with_var("x", 6.0);
+    let cases = [
+        ("2 + 3 * 4", "14"),
+        ("(7 - 10) * 2", "-6"),
+        ("2^10 - 1", "1023"),
+        ("-7 // 2", "-4"),
+        ("-7 mod 2", "1"),
+        ("-7 rem 2", "-1"),
+        ("x / 3", "2"),
+        ("5!", "120"),
+        ("200 + 10%", "220"),
+        ("0xF0 & 0x3C | 1", "49"),
+        ("1 << 10 >> 2", "256"),
+        ("~5 xor 3", "-7"),
+        ("x > 5 && 2 == 2", "true"),
+        ("x > 10 ? 1 : 0", "0"),
+    ];
+    for (expr, expected) in cases {
+        let value = evaluator.evaluate_as::<N>(expr).unwrap();
+        assert_eq!(value.to_string(), expected, "{} with {}", expr, N::NAME);
+    }
+    let error = |expr| match evaluator.evaluate_as::<N>(expr) {
+        Err(Error::Eval(err)) => err,
+        other => panic!("{} with {}: expected an error, got {:?}", expr, N::NAME, other),
+    };
+    assert!(matches!(error("1 / (x - 6)"), EvalError::DivisionByZero { .. }));
+    assert!(matches!(error("0 ^ -1"), EvalError::ZeroToNegativePower { .. }));
+    assert!(matches!(error("(-1)!"), EvalError::DomainError { .. }));
+    assert!(matches!(error("1 << -1"), EvalError::ShiftOutOfRange { .. }));
+    assert!(matches!(error("true + 1"), EvalError::TypeMismatch { .. }));
+}
+
+// A number type defined by the application: integers that clamp instead of overflowing
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+struct Saturating(i32);
+
+impl std::fmt::Display for Saturating {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl Numeric for Saturating {
+    const NAME: &'static str = "saturating";
+
+    fn from_f64(value: f64) -> Option<Self> {
+        (value.fract() == 0.0).then_some(Saturating(value as i32))
+    }
+    fn from_u128(value: u128) -> Option<Self> {
+        Some(Saturating(i32::try_from(value).unwrap_or(i32::MAX)))
+    }
+    fn to_f64(&self) -> f64 {
+        self.0 as f64
+    }
+    fn is_zero(&self) -> bool {
+        self.0 == 0
+    }
+
+    fn add(&self, rhs: &Self) -> Result<Self, NumError> {
+        Ok(Saturating(self.0.saturating_add(rhs.0)))
+    }
+    fn sub(&self, rhs: &Self) -> Result<Self, NumError> {
+        Ok(Saturating(self.0.saturating_sub(rhs.0)))
+    }
+    fn mul(&self, rhs: &Self) -> Result<Self, NumError> {
+        Ok(Saturating(self.0.saturating_mul(rhs.0)))
+    }
+    fn neg(&self) -> Result<Self, NumError> {
+        Ok(Saturating(self.0.saturating_neg()))
+    }
+    fn div(&self, rhs: &Self) -> Result<Self, NumError> {
+        if self.0 % rhs.0 != 0 {
+            return Err(NumError::Inexact);
+        }
+        Ok(Saturating(self.0.saturating_div(rhs.0)))
+    }
+    fn rem(&self, rhs: &Self) -> Result<Self, NumError> {
+        Ok(Saturating(self.0.wrapping_rem(rhs.0)))
+    }
+    fn pow(&self, exponent: &Self) -> Result<Self, NumError> {
+        let exponent = u32::try_from(exponent.0).map_err(|_| NumError::Inexact)?;
+        Ok(Saturating(self.0.saturating_pow(exponent)))
+    }
+
+    fn bit_and(&self, rhs: &Self) -> Result<Self, NumError> {
+        Ok(Saturating(self.0 & rhs.0))
+    }
+    fn bit_or(&self, rhs: &Self) -> Result<Self, NumError> {
+        Ok(Saturating(self.0 | rhs.0))
+    }
+    fn bit_xor(&self, rhs: &Self) -> Result<Self, NumError> {
+        Ok(Saturating(self.0 ^ rhs.0))
+    }
+    fn bit_not(&self) -> Result<Self, NumError> {
+        Ok(Saturating(!self.0))
+    }
+    fn shl(&self, amount: u32) -> Result<Self, NumError> {
+        self.0.checked_shl(amount).map(Saturating).ok_or(NumError::Overflow)
+    }
+    fn shr(&self, amount: u32) -> Result<Self, NumError> {
+        self.0.checked_shr(amount).map(Saturating).ok_or(NumError::Overflow)
+    }
+}
+
+#[test]
+fn test_shared_numeric_suite() {
+    check_shared_suite::<f64>();
+    check_shared_suite::<i64>();
+    check_shared_suite::<i128>();
+    check_shared_suite::<Rational>();
+    check_shared_suite::<Decimal>();
+    check_shared_suite::<Saturating>();
+    #[cfg(feature = "bignum")]
+    check_shared_suite::<expression_eval::parsemath::bignum::BigInteger>();
+}
+
+#[test]
+fn test_custom_numeric_type() {
+    let evaluator = Evaluator::new();
+    let value = evaluator.evaluate_as::<Saturating>("2^40 + 1").unwrap();
+    assert_eq!(value, Saturating(i32::MAX));
+    let value = evaluator.evaluate_as::<Saturating>("-(2^31) * 3").unwrap();
+    assert_eq!(value, Saturating(i32::MIN));
+    assert!(matches!(
+        evaluator.evaluate_as::<Saturating>("1 << 40"),
+        Err(Error::Eval(EvalError::NumericOverflow { op: "<<", ty: "saturating", .. }))
+    ));
+    assert!(matches!(
+        evaluator.evaluate_as::<Saturating>("7 / 2"),
+        Err(Error::Eval(EvalError::InexactResult { op: "/", .. }))
+    ));
+}